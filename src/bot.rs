@@ -0,0 +1,120 @@
+use crate::commands::{Commands, Movement};
+use crate::world::boss::{Boss, BOSS_HALF_WIDTH};
+use crate::world::obstacles::{lane_z, LANES};
+use crate::world::{World, TUNNEL_HALF_WIDTH};
+
+/// A player that decides its commands by looking at the world, one tick at a time.
+pub trait Bot {
+    fn commands(&mut self, world: &World) -> Commands;
+}
+
+pub fn bot_from_name(name: &str) -> Option<Box<dyn Bot>> {
+    match name {
+        "forward" => Some(Box::new(ForwardBot)),
+        "greedy" => Some(Box::new(GreedyBot)),
+        _ => None,
+    }
+}
+
+pub const BOT_NAMES: [&str; 2] = ["forward", "greedy"];
+
+/// Runs straight ahead and never dodges. Useful as a baseline.
+pub struct ForwardBot;
+
+impl Bot for ForwardBot {
+    fn commands(&mut self, world: &World) -> Commands {
+        Commands {
+            forward_movement: Movement::Positive,
+            ..Commands::idle(world.now_ts)
+        }
+    }
+}
+
+/// Runs ahead switching to whichever lane is free for the longest stretch, jumping or pissing
+/// at obstacles it can't dodge in time.
+pub struct GreedyBot;
+
+const LOOK_AHEAD: f32 = 8.0;
+const OBSTACLE_REACH: f32 = 0.9;
+const PISS_RANGE: f32 = 2.5;
+const BOSS_FIGHT_DISTANCE: f32 = 2.0;
+
+impl Bot for GreedyBot {
+    fn commands(&mut self, world: &World) -> Commands {
+        if let Some(boss) = world.boss.as_ref().filter(|boss| !boss.is_defeated()) {
+            return fight_boss(world, boss);
+        }
+        let clearances: Vec<f32> = (0..LANES).map(|i| clearance(world, lane_z(i))).collect();
+        let mut target_lane = nearest_lane(world.player_pos.z);
+        for i_lane in 0..LANES {
+            let lane_clearance = clearances[i_lane as usize];
+            let target_clearance = clearances[target_lane as usize];
+            let is_closer = (lane_z(i_lane) - world.player_pos.z).abs()
+                < (lane_z(target_lane) - world.player_pos.z).abs();
+            if lane_clearance > target_clearance
+                || (lane_clearance == target_clearance && is_closer)
+            {
+                target_lane = i_lane;
+            }
+        }
+        let dz = lane_z(target_lane) - world.player_pos.z;
+        let left_movement = if dz.abs() < 0.05 {
+            Movement::None
+        } else {
+            Movement::from_value(-dz)
+        };
+        let current_clearance = clearance(world, world.player_pos.z);
+        Commands {
+            forward_movement: Movement::Positive,
+            left_movement,
+            jump: current_clearance < 1.2 || projectile_close(world),
+            pissing: current_clearance < PISS_RANGE,
+            ..Commands::idle(world.now_ts)
+        }
+    }
+}
+
+/// Stands in piss range in front of the boss, jumping over its projectiles.
+fn fight_boss(world: &World, boss: &Boss) -> Commands {
+    let distance = boss.pos.x - BOSS_HALF_WIDTH - world.player_pos.x;
+    let dz = boss.pos.z - world.player_pos.z;
+    Commands {
+        forward_movement: Movement::from_value(distance - BOSS_FIGHT_DISTANCE),
+        left_movement: Movement::from_value(-dz),
+        jump: projectile_close(world),
+        pissing: distance < PISS_RANGE,
+        ..Commands::idle(world.now_ts)
+    }
+}
+
+/// Whether a projectile is about to hit the player, unless it jumps.
+fn projectile_close(world: &World) -> bool {
+    world.projectiles.iter().any(|projectile| {
+        let gap = projectile.position - world.player_pos;
+        gap.x > -OBSTACLE_REACH && gap.x < 1.5 && gap.z.abs() < 1.5 && gap.length() < 1.5
+    })
+}
+
+/// Distance to the nearest alive obstacle ahead of the player that blocks the given lane.
+fn clearance(world: &World, z: f32) -> f32 {
+    let z = z.clamp(-TUNNEL_HALF_WIDTH, TUNNEL_HALF_WIDTH);
+    world
+        .obstacles
+        .iter()
+        .filter(|obstacle| obstacle.is_alive())
+        .map(|obstacle| obstacle.get_pos(world.now_ts))
+        .filter(|pos| (pos.z - z).abs() < OBSTACLE_REACH)
+        .map(|pos| pos.x - world.player_pos.x)
+        .filter(|distance| *distance > -OBSTACLE_REACH && *distance < LOOK_AHEAD)
+        .fold(LOOK_AHEAD, f32::min)
+}
+
+fn nearest_lane(z: f32) -> i32 {
+    let mut nearest = 0;
+    for i_lane in 0..LANES {
+        if (lane_z(i_lane) - z).abs() < (lane_z(nearest) - z).abs() {
+            nearest = i_lane;
+        }
+    }
+    nearest
+}
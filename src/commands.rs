@@ -0,0 +1,47 @@
+use crate::common::TimestampSeconds;
+use serde::{Deserialize, Serialize};
+
+/// What the player wants to do during a frame, regardless of the input device it came from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Commands {
+    #[serde(default)]
+    pub pause: bool,
+    pub forward_movement: Movement,
+    pub left_movement: Movement,
+    pub jump: bool,
+    pub ts_now: TimestampSeconds,
+    pub pissing: bool,
+}
+
+impl Commands {
+    /// Doing nothing at `ts_now`.
+    pub fn idle(ts_now: TimestampSeconds) -> Self {
+        Self {
+            pause: false,
+            forward_movement: Movement::None,
+            left_movement: Movement::None,
+            jump: false,
+            ts_now,
+            pissing: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Movement {
+    None,
+    Positive,
+    Negative,
+}
+
+impl Movement {
+    pub fn from_value(value: f32) -> Movement {
+        if value == 0.0 {
+            Movement::None
+        } else if value > 0.0 {
+            Movement::Positive
+        } else {
+            Movement::Negative
+        }
+    }
+}
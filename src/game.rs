@@ -0,0 +1,206 @@
+//! Flow of the game around the world simulation: which screen is up, and where the world's events
+//! and the player's choices lead from there.
+
+use crate::commands::Commands;
+use crate::common::TimestampSeconds;
+use crate::replay::Frame;
+use crate::world::timestep::FixedTimestep;
+use crate::world::{Event, Transition, World};
+
+/// A gap between frames this long means the window was hidden or in the background, e.g. a
+/// browser tab that stopped drawing, so the player was not watching.
+pub const FOCUS_LOST_GAP: TimestampSeconds = 1.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameState {
+    /// Main menu, before playing.
+    Menu,
+    Playing,
+    /// The world is frozen until the player resumes.
+    Paused,
+    /// The level is finished, waiting for the player to continue to the next one.
+    LevelComplete,
+    /// The player died, waiting for them to restart.
+    GameOver,
+}
+
+/// What the player picked in a menu or dialog.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Choice {
+    /// Changes level, then plays it.
+    Transition(Transition),
+    Resume,
+    /// Leaves the run for the main menu.
+    MainMenu,
+    Quit,
+}
+
+pub struct Game {
+    pub state: GameState,
+    pub world: World,
+    timestep: FixedTimestep,
+}
+
+impl Game {
+    pub fn new(world: World, start_ts: TimestampSeconds) -> Self {
+        Self {
+            state: GameState::Playing,
+            world,
+            timestep: FixedTimestep::new(start_ts),
+        }
+    }
+
+    /// Runs the world for the time since the previous frame, unless it is frozen, and follows
+    /// its events.
+    pub fn update(&mut self, commands: Commands) {
+        if commands.pause {
+            self.toggle_pause();
+        }
+        if commands.ts_now - self.timestep.previous_frame_ts() > FOCUS_LOST_GAP {
+            self.pause();
+        }
+        if self.is_world_frozen() {
+            // the world's clock only moves with its ticks, so obstacles pick up where they were
+            self.timestep.restart_from(commands.ts_now);
+            return;
+        }
+        self.advance(commands);
+    }
+
+    fn advance(&mut self, commands: Commands) {
+        for event in self.timestep.advance(&mut self.world, commands) {
+            self.state = match event {
+                Event::LevelFinished => GameState::LevelComplete,
+                Event::GameOver => GameState::GameOver,
+            };
+        }
+    }
+
+    /// Runs the ticks of a recorded frame, or none if the world was frozen during it.
+    pub fn play_back(&mut self, frame: &Frame) {
+        if frame.frozen {
+            self.restart_from(frame.commands.ts_now);
+        } else {
+            self.advance(frame.commands.clone());
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            state => state,
+        };
+    }
+
+    /// Freezes the world while playing, e.g. when the window loses focus.
+    pub fn pause(&mut self) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state == GameState::Paused {
+            self.state = GameState::Playing;
+        }
+    }
+
+    pub fn open_menu(&mut self) {
+        self.state = GameState::Menu;
+    }
+
+    /// Changes level and goes back to playing.
+    pub fn apply(&mut self, transition: Transition) {
+        self.world.apply(transition);
+        self.state = GameState::Playing;
+    }
+
+    /// Continues from `ts` without simulating the time elapsed since the previous frame.
+    pub fn restart_from(&mut self, ts: TimestampSeconds) {
+        self.timestep.restart_from(ts);
+    }
+
+    /// Whether the world stands still, e.g. behind a menu.
+    pub fn is_world_frozen(&self) -> bool {
+        matches!(self.state, GameState::Menu | GameState::Paused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Movement;
+    use crate::world::obstacles::Obstacle;
+    use glam::Vec3;
+
+    fn forward(ts_now: TimestampSeconds) -> Commands {
+        Commands {
+            forward_movement: Movement::Positive,
+            ..Commands::idle(ts_now)
+        }
+    }
+
+    #[test]
+    fn test_events_drive_the_state() {
+        let mut world = World::with_seed(0, 1234);
+        world.obstacles.clear();
+        world.finish_x = 1.0;
+        let mut game = Game::new(world, 0.0);
+        game.update(forward(0.5));
+        assert_eq!(game.state, GameState::LevelComplete);
+        game.apply(Transition::NextLevel);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.world.level, 1);
+
+        game.world.health = 0.01;
+        let pos = game.world.player_pos;
+        game.world
+            .obstacles
+            .push_back(Obstacle::new(pos.x + 0.5, 0.0, pos.z));
+        game.update(forward(1.0));
+        assert_eq!(game.state, GameState::GameOver);
+    }
+
+    #[test]
+    fn test_paused_world_picks_up_where_it_was() {
+        let mut paused = Game::new(World::with_seed(5, 1234), 0.0);
+        paused.update(forward(0.5));
+        paused.update(Commands {
+            pause: true,
+            ..forward(0.6)
+        });
+        assert_eq!(paused.state, GameState::Paused);
+        paused.update(forward(10.0));
+        paused.resume();
+        paused.update(forward(10.4));
+
+        let mut unpaused = Game::new(World::with_seed(5, 1234), 0.0);
+        unpaused.update(forward(0.5));
+        unpaused.update(forward(0.9));
+        assert_eq!(paused.world.now_ts, unpaused.world.now_ts);
+        assert_eq!(paused.world.player_pos, unpaused.world.player_pos);
+        assert_eq!(obstacle_positions(&paused), obstacle_positions(&unpaused));
+    }
+
+    #[test]
+    fn test_long_gap_pauses_without_running_the_world() {
+        let mut game = Game::new(World::with_seed(0, 1234), 0.0);
+        game.update(forward(0.5));
+        let now_ts = game.world.now_ts;
+        game.update(forward(60.0));
+        assert_eq!(game.state, GameState::Paused);
+        assert_eq!(game.world.now_ts, now_ts);
+        game.resume();
+        game.update(forward(60.1));
+        assert!(game.world.now_ts - now_ts < 0.15);
+    }
+
+    fn obstacle_positions(game: &Game) -> Vec<Vec3> {
+        game.world
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.get_pos(game.world.now_ts))
+            .collect()
+    }
+}
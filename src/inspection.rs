@@ -0,0 +1,70 @@
+use crate::world::behaviour::Behaviour;
+use crate::world::level::Level;
+use crate::world::obstacles::LANES;
+use std::collections::BTreeMap;
+
+/// Numbers to judge a level's difficulty without playing it.
+#[derive(PartialEq, Debug)]
+pub struct LevelStats {
+    pub obstacles: usize,
+    pub moving_obstacles: usize,
+    /// Rows where every lane has a static obstacle, so the player has to jump or piss through.
+    pub fully_blocked_rows: usize,
+    pub course_length: f32,
+}
+
+impl LevelStats {
+    pub fn new(level: &Level) -> Self {
+        let mut lanes_per_row = BTreeMap::<i32, usize>::new();
+        let mut moving_obstacles = 0;
+        for obstacle in &level.obstacles {
+            match obstacle.behaviour() {
+                Behaviour::Static => {
+                    let row = obstacle.origin().x.round() as i32;
+                    *lanes_per_row.entry(row).or_default() += 1;
+                }
+                _ => moving_obstacles += 1,
+            }
+        }
+        Self {
+            obstacles: level.obstacles.len(),
+            moving_obstacles,
+            fully_blocked_rows: lanes_per_row
+                .values()
+                .filter(|lanes| **lanes >= LANES as usize)
+                .count(),
+            course_length: level.finish_x,
+        }
+    }
+
+    pub fn moving_ratio(&self) -> f32 {
+        if self.obstacles == 0 {
+            0.0
+        } else {
+            self.moving_obstacles as f32 / self.obstacles as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::ascii;
+
+    #[test]
+    fn test_stats() {
+        let text = "....\n....\n####\n.#..\n>...\n####\n";
+        let level = ascii::parse(text).unwrap().to_level();
+        let stats = LevelStats::new(&level);
+        assert_eq!(
+            stats,
+            LevelStats {
+                obstacles: 10,
+                moving_obstacles: 1,
+                fully_blocked_rows: 2,
+                course_length: 15.0,
+            }
+        );
+        assert_eq!(stats.moving_ratio(), 0.1);
+    }
+}
@@ -0,0 +1,12 @@
+//! Rules of the game, usable without opening a window.
+
+pub mod bot;
+pub mod clock;
+pub mod commands;
+pub mod common;
+pub mod game;
+pub mod inspection;
+pub mod progress;
+pub mod replay;
+pub mod simulation;
+pub mod world;
@@ -1,44 +1,365 @@
-mod common;
 mod screen;
-mod world;
 
-use crate::common::AnyError;
+use crate::screen::clock::RealClock;
 use crate::screen::commands::get_commands;
 use crate::screen::draw::Drawer;
+use crate::screen::focus;
 use crate::screen::models::load_models;
-use crate::world::World;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use corporation_slayer::bot::{bot_from_name, BOT_NAMES};
+use corporation_slayer::clock::Clock;
+use corporation_slayer::common::{AnyError, TimestampSeconds};
+use corporation_slayer::game::{Choice, Game, GameState};
+use corporation_slayer::inspection::LevelStats;
+use corporation_slayer::progress::Progress;
+use corporation_slayer::replay::{Frame, Replay};
+use corporation_slayer::simulation::{simulate_bot, simulate_replay};
+use corporation_slayer::world::ascii;
+use corporation_slayer::world::difficulty::Difficulty;
+use corporation_slayer::world::level::{Generator, Level, LevelDescription};
+use corporation_slayer::world::{get_random_seed, World};
 use git_version::git_version;
 use macroquad::prelude::*;
 
 const GIT_VERSION: &str = git_version!(args = ["--tags", "--dirty"]);
-const DEFAULT_WINDOW_TITLE: &'static str = "Corporation slayer";
+/// Where the highest unlocked level is saved, which is a local storage key in browsers.
+const PROGRESS_PATH: &str = "corporation_slayer_progress.json";
+const DEFAULT_WINDOW_TITLE: &str = "Corporation slayer";
 const DEFAULT_WINDOW_WIDTH: i32 = 480;
 const DEFAULT_WINDOW_HEIGHT: i32 = 640;
+const DIFFICULTY_HELP: &str =
+    "Difficulty preset (easy, normal or hard), or a JSON file with a custom difficulty profile.";
+const ENDLESS_HELP: &str =
+    "Run through a single endless course that gets harder with the distance, instead of levels.";
 
-#[macroquad::main(window_conf)]
-async fn main() -> Result<(), AnyError> {
+fn main() -> Result<(), AnyError> {
     let args = CliArgs::parse();
+    match args.command {
+        Some(Command::Simulate(simulate_args)) => simulate(simulate_args),
+        Some(Command::Inspect(inspect_args)) => inspect(inspect_args),
+        None => {
+            macroquad::Window::from_config(window_conf(), async {
+                if let Err(err) = play(args).await {
+                    error!("Error: {:?}", err);
+                }
+            });
+            Ok(())
+        }
+    }
+}
+
+async fn play(args: CliArgs) -> Result<(), AnyError> {
     let models = load_models()?;
-    let mut world = World::new(args.level);
-    let mut drawer = Drawer::new();
+    let clock = RealClock;
+    let replay = args.replay.as_deref().map(Replay::load).transpose()?;
+    let world = match &replay {
+        Some(replay) => replay.new_world(),
+        None => {
+            let seed = args.seed.unwrap_or_else(|| get_random_seed(&clock));
+            let difficulty = Difficulty::load(&args.difficulty)?;
+            if args.endless {
+                World::endless(seed, difficulty)
+            } else {
+                World::with_difficulty(
+                    args.level,
+                    seed,
+                    args.level_file
+                        .as_deref()
+                        .map(LevelDescription::load)
+                        .transpose()?,
+                    difficulty,
+                )
+            }
+        }
+    };
+    let start_ts = replay.as_ref().map_or_else(|| clock.now(), |r| r.start_ts);
+    let mut recording = args.record.as_ref().map(|_| Replay::new(&world, start_ts));
+    let mut drawer = Drawer::new(&world);
+    let mut game = Game::new(world, start_ts);
+    if replay.is_none() {
+        game.state = GameState::Menu;
+    }
+    let mut playback = replay.map(|replay| replay.frames.into_iter());
+    let mut progress = Progress::load(PROGRESS_PATH).unwrap_or_else(|err| {
+        warn!("Could not load the progress: {:?}", err);
+        Progress::default()
+    });
     loop {
-        let commands = get_commands();
-        if commands.should_quit {
-            break;
+        let live_commands = get_commands(&clock, game.state == GameState::Playing);
+        let was_game_over = game.state == GameState::GameOver;
+        let recorded = match playback.as_mut().and_then(Iterator::next) {
+            Some(frame) => {
+                game.play_back(&frame);
+                Some(frame)
+            }
+            None => {
+                if playback.take().is_some() {
+                    // the replay is over, the player takes control from here
+                    game.restart_from(live_commands.ts_now);
+                }
+                if !focus::has_focus() {
+                    // alt-tabbing away should never cost the player a life
+                    game.pause();
+                }
+                game.update(live_commands.clone());
+                None
+            }
+        };
+        // pausing happens within the update, so the state after it tells whether the world ran
+        let frozen = recorded
+            .as_ref()
+            .map_or_else(|| game.is_world_frozen(), |frame| frame.frozen);
+        let died = !was_game_over && game.state == GameState::GameOver;
+        let choice = drawer.draw(&game, &progress, &models, &clock);
+        let (commands, choice) = match recorded {
+            // only the recording changes the run while it plays back, or it would drift away
+            Some(frame) => (
+                frame.commands,
+                match choice {
+                    Some(Choice::Quit) => Some(Choice::Quit),
+                    _ => frame.transition.map(Choice::Transition),
+                },
+            ),
+            None => (live_commands, choice),
+        };
+        let transition = match choice {
+            Some(Choice::Transition(transition)) => Some(transition),
+            Some(Choice::Resume) => {
+                game.resume();
+                None
+            }
+            Some(Choice::MainMenu) => {
+                game.open_menu();
+                None
+            }
+            Some(Choice::Quit) => break,
+            None => None,
+        };
+        if let Some(recording) = &mut recording {
+            recording.push(Frame {
+                commands,
+                transition,
+                frozen,
+            });
+            if died {
+                save_recording(recording, &args)?;
+            }
+        }
+        if let Some(transition) = transition {
+            game.apply(transition);
+            if progress.unlock(&game.world) {
+                if let Err(err) = progress.save(PROGRESS_PATH) {
+                    warn!("Could not save the progress: {:?}", err);
+                }
+            }
         }
-        world.update(commands);
-        drawer.draw(&mut world, &models);
         next_frame().await
     }
+    if let Some(recording) = &recording {
+        save_recording(recording, &args)?;
+    }
+    Ok(())
+}
+
+fn save_recording(recording: &Replay, args: &CliArgs) -> Result<(), AnyError> {
+    if let Some(path) = &args.record {
+        recording.save(path)?;
+    }
+    Ok(())
+}
+
+/// Runs without a window and prints one JSON summary per run.
+fn simulate(args: SimulateArgs) -> Result<(), AnyError> {
+    if let Some(path) = &args.replay {
+        let summary = simulate_replay(&Replay::load(path)?);
+        println!("{}", serde_json::to_string(&summary)?);
+        return Ok(());
+    }
+    let first_seed = args.seed.unwrap_or_else(|| get_random_seed(&RealClock));
+    let authored_level = args
+        .level_file
+        .as_deref()
+        .map(LevelDescription::load)
+        .transpose()?;
+    let difficulty = Difficulty::load(&args.difficulty)?;
+    for run in 0..args.runs {
+        let mut bot = bot_from_name(&args.bot).ok_or_else(|| {
+            format!(
+                "unknown bot {:?}, expected one of {:?}",
+                args.bot, BOT_NAMES
+            )
+        })?;
+        let seed = first_seed.wrapping_add(run);
+        let world = if args.endless {
+            World::endless(seed, difficulty)
+        } else {
+            World::with_difficulty(args.level, seed, authored_level.clone(), difficulty)
+        };
+        let summary = simulate_bot(world, args.levels, args.max_time, bot.as_mut());
+        println!("{}", serde_json::to_string(&summary)?);
+    }
+    Ok(())
+}
+
+/// Prints the generated layout of each level in the range, with some statistics.
+fn inspect(args: InspectArgs) -> Result<(), AnyError> {
+    let seed = args.seed.unwrap_or_else(|| get_random_seed(&RealClock));
+    let last_level = args.last_level.unwrap_or(args.first_level);
+    let difficulty = Difficulty::load(&args.difficulty)?;
+    for level_number in args.first_level..=last_level {
+        println!(
+            "level {} (--level {} --seed {})",
+            level_number + 1,
+            level_number,
+            seed
+        );
+        if let Some(boss_number) = difficulty.boss_number(level_number) {
+            let level = Level::boss(boss_number);
+            if let Some(boss) = &level.boss {
+                println!("boss {} with {:.0} hp", boss_number, boss.max_hp());
+            }
+            println!("course length: {:.1}", level.finish_x);
+            println!();
+            continue;
+        }
+        let generator = Generator::for_level(seed, &difficulty);
+        let level = Level::generate_with(generator, level_number, seed, &difficulty);
+        let stats = LevelStats::new(&level);
+        print!("{}", ascii::export(&level.obstacles));
+        println!("generator: {:?}", generator);
+        println!("obstacles: {}", stats.obstacles);
+        println!(
+            "moving obstacles: {} ({:.0}%)",
+            stats.moving_obstacles,
+            stats.moving_ratio() * 100.0
+        );
+        println!("fully blocked rows: {}", stats.fully_blocked_rows);
+        println!("course length: {:.1}", stats.course_length);
+        println!();
+    }
     Ok(())
 }
 
 #[derive(Parser, Debug)]
 #[clap(version = GIT_VERSION)]
 struct CliArgs {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(long, help = "Starting level.", default_value = "0")]
+    level: i32,
+
+    #[clap(long, help = "Seed of the starting level layout. Random if not given.")]
+    seed: Option<u64>,
+
+    #[clap(
+        long,
+        help = "Start with the level in this JSON file (or plain-text if it ends in .txt)."
+    )]
+    level_file: Option<String>,
+
+    #[clap(long, help = DIFFICULTY_HELP, default_value = "normal")]
+    difficulty: String,
+
+    #[clap(long, help = ENDLESS_HELP, conflicts_with_all = &["level", "level-file"])]
+    endless: bool,
+
+    #[clap(long, help = "Save the run to this replay file, overwriting it.")]
+    record: Option<String>,
+
+    #[clap(
+        long,
+        help = "Play back this replay file, then let the player take control."
+    )]
+    replay: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[clap(about = "Play without a window, printing a JSON summary per run.")]
+    Simulate(SimulateArgs),
+
+    #[clap(about = "Print the generated layouts of a range of levels, with statistics.")]
+    Inspect(InspectArgs),
+}
+
+#[derive(Parser, Debug)]
+struct InspectArgs {
+    #[clap(long, help = "First level to print.", default_value = "0")]
+    first_level: i32,
+
+    #[clap(long, help = "Last level to print. Defaults to the first level.")]
+    last_level: Option<i32>,
+
+    #[clap(long, help = "Seed of every level's layout. Random if not given.")]
+    seed: Option<u64>,
+
+    #[clap(long, help = DIFFICULTY_HELP, default_value = "normal")]
+    difficulty: String,
+}
+
+#[derive(Parser, Debug)]
+struct SimulateArgs {
     #[clap(long, help = "Starting level.", default_value = "0")]
     level: i32,
+
+    #[clap(
+        long,
+        help = "Seed of the first run. Following runs use the next seeds. Random if not given."
+    )]
+    seed: Option<u64>,
+
+    #[clap(
+        long,
+        help = "Start with the level in this JSON file (or plain-text if it ends in .txt)."
+    )]
+    level_file: Option<String>,
+
+    #[clap(long, help = DIFFICULTY_HELP, default_value = "normal")]
+    difficulty: String,
+
+    #[clap(long, help = ENDLESS_HELP, conflicts_with_all = &["level", "level-file"])]
+    endless: bool,
+
+    #[clap(
+        long,
+        help = "Player to simulate: forward or greedy.",
+        default_value = "greedy"
+    )]
+    bot: String,
+
+    #[clap(
+        long,
+        help = "Stop after clearing this many levels.",
+        default_value = "1"
+    )]
+    levels: i32,
+
+    #[clap(long, help = "Number of runs.", default_value = "1")]
+    runs: u64,
+
+    #[clap(
+        long,
+        help = "Give up a run after this many seconds of game time.",
+        default_value = "600"
+    )]
+    max_time: TimestampSeconds,
+
+    #[clap(
+        long,
+        help = "Play back this replay file instead of a bot.",
+        conflicts_with_all = &[
+            "bot",
+            "seed",
+            "level",
+            "level-file",
+            "difficulty",
+            "endless",
+            "levels",
+            "runs",
+        ]
+    )]
+    replay: Option<String>,
 }
 
 fn window_conf() -> Conf {
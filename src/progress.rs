@@ -0,0 +1,146 @@
+//! What the player unlocked so far, kept between sessions: in a file on desktop, and in the
+//! browser's local storage on the web, through the plugin registered in `export_html/index.html`.
+
+use crate::common::AnyError;
+use crate::world::{Mode, World};
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::ErrorKind;
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug)]
+pub struct Progress {
+    /// Highest level reached so far, starting at 0.
+    pub highest_level: i32,
+}
+
+impl Progress {
+    /// Loads the saved progress, or starts from scratch if nothing was saved yet. On the web,
+    /// `path` is the local storage key.
+    pub fn load(path: &str) -> Result<Self, AnyError> {
+        match storage::read(path)? {
+            Some(text) => Ok(serde_json::from_str(&text)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), AnyError> {
+        storage::write(path, &serde_json::to_string(self)?)
+    }
+
+    /// Unlocks the level the world is at. Returns whether it was still locked.
+    pub fn unlock(&mut self, world: &World) -> bool {
+        if world.mode != Mode::Levels || world.level <= self.highest_level {
+            return false;
+        }
+        self.highest_level = world.level;
+        true
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use super::*;
+
+    pub fn read(path: &str) -> Result<Option<String>, AnyError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn write(path: &str, text: &str) -> Result<(), AnyError> {
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use super::*;
+
+    extern "C" {
+        /// Copies the value at `key` into the buffer if it fits. Returns its length in bytes, or
+        /// -1 if there is no such key.
+        fn corporation_slayer_storage_get(
+            key: *const u8,
+            key_len: usize,
+            buffer: *mut u8,
+            buffer_len: usize,
+        ) -> i32;
+        /// Returns whether the browser stored it, which fails e.g. when the storage is full.
+        fn corporation_slayer_storage_set(
+            key: *const u8,
+            key_len: usize,
+            value: *const u8,
+            value_len: usize,
+        ) -> bool;
+    }
+
+    /// Version of the JS plugin this expects, checked by the miniquad loader.
+    #[no_mangle]
+    pub extern "C" fn corporation_slayer_storage_crate_version() -> u32 {
+        1 << 16 // 0.1.0
+    }
+
+    pub fn read(key: &str) -> Result<Option<String>, AnyError> {
+        let mut buffer = vec![0; 256];
+        loop {
+            let len = unsafe {
+                corporation_slayer_storage_get(
+                    key.as_ptr(),
+                    key.len(),
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                )
+            };
+            if len < 0 {
+                return Ok(None);
+            }
+            let len = len as usize;
+            if len <= buffer.len() {
+                buffer.truncate(len);
+                return Ok(Some(String::from_utf8(buffer)?));
+            }
+            buffer.resize(len, 0);
+        }
+    }
+
+    pub fn write(key: &str, text: &str) -> Result<(), AnyError> {
+        let stored = unsafe {
+            corporation_slayer_storage_set(key.as_ptr(), key.len(), text.as_ptr(), text.len())
+        };
+        if stored {
+            Ok(())
+        } else {
+            Err(format!("the browser could not store {:?}", key).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::difficulty::Difficulty;
+
+    #[test]
+    fn test_progress_survives_a_save() {
+        let path = std::env::temp_dir().join("corporation_slayer_test_progress.json");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let mut progress = Progress::load(path).unwrap();
+        assert_eq!(progress, Progress::default());
+
+        let mut world = World::with_seed(0, 1234);
+        world.next_level();
+        world.next_level();
+        assert!(progress.unlock(&world));
+        assert!(!progress.unlock(&World::with_seed(1, 1234)));
+        assert!(!progress.unlock(&World::endless(1234, Difficulty::default())));
+        progress.save(path).unwrap();
+        assert_eq!(Progress::load(path).unwrap().highest_level, 2);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use crate::commands::Commands;
+use crate::common::{AnyError, TimestampSeconds};
+use crate::world::difficulty::Difficulty;
+use crate::world::level::LevelDescription;
+use crate::world::{Mode, Transition, World};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Everything needed to reproduce a run: where it started and what happened on each frame.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Replay {
+    pub level: i32,
+    pub seed: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authored_level: Option<LevelDescription>,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub mode: Mode,
+    pub start_ts: TimestampSeconds,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Frame {
+    pub commands: Commands,
+    /// Level change chosen in a dialog after this frame's ticks, if any.
+    pub transition: Option<Transition>,
+    /// The world stood still during this frame, e.g. behind a menu.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub frozen: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl Replay {
+    pub fn new(world: &World, start_ts: TimestampSeconds) -> Self {
+        Self {
+            level: world.level,
+            seed: world.seed,
+            authored_level: world.authored_level.clone(),
+            difficulty: world.difficulty,
+            mode: world.mode,
+            start_ts,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn new_world(&self) -> World {
+        match self.mode {
+            Mode::Levels => World::with_difficulty(
+                self.level,
+                self.seed,
+                self.authored_level.clone(),
+                self.difficulty,
+            ),
+            Mode::Endless => World::endless(self.seed, self.difficulty),
+        }
+    }
+
+    /// Adds a frame. A run of frozen frames only matters for when it ends, so they replace each
+    /// other until one of them changes level.
+    pub fn push(&mut self, frame: Frame) {
+        match self.frames.last_mut() {
+            Some(last) if last.frozen && last.transition.is_none() && frame.frozen => *last = frame,
+            _ => self.frames.push(frame),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, AnyError> {
+        let text = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), AnyError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Movement;
+    use crate::game::Game;
+
+    #[test]
+    fn test_wall_clock_timestamps_survive_a_save() {
+        let start_ts = 1_700_000_000.0;
+        let mut replay = Replay::new(&World::with_seed(0, 1234), start_ts);
+        let mut ts = start_ts;
+        for i in 0..10_000 {
+            ts += 0.0161 + (i % 13) as f64 * 0.000_37;
+            replay.frames.push(Frame {
+                commands: Commands::idle(ts),
+                transition: None,
+                frozen: false,
+            });
+        }
+        let text = serde_json::to_string(&replay).unwrap();
+        let loaded: Replay = serde_json::from_str(&text).unwrap();
+        for (frame, loaded_frame) in replay.frames.iter().zip(&loaded.frames) {
+            assert_eq!(frame.commands.ts_now, loaded_frame.commands.ts_now);
+        }
+    }
+
+    fn play(replay: &Replay) -> World {
+        let mut game = Game::new(replay.new_world(), replay.start_ts);
+        for frame in &replay.frames {
+            game.play_back(frame);
+            if let Some(transition) = frame.transition {
+                game.apply(transition);
+            }
+        }
+        game.world
+    }
+
+    #[test]
+    fn test_replay_reproduces_run() {
+        let mut replay = Replay::new(&World::with_seed(3, 1234), 100.0);
+        let mut ts = 100.0;
+        for i in 0..300 {
+            ts += 0.013 + (i % 7) as f64 * 0.001;
+            replay.frames.push(Frame {
+                commands: Commands {
+                    forward_movement: Movement::Positive,
+                    left_movement: Movement::from_value((i % 40 - 20) as f32),
+                    jump: i % 50 == 0,
+                    pissing: i % 3 == 0,
+                    ..Commands::idle(ts)
+                },
+                transition: (i == 200).then_some(Transition::NextLevel),
+                frozen: (100..150).contains(&i),
+            });
+        }
+        let text = serde_json::to_string(&replay).unwrap();
+        let loaded: Replay = serde_json::from_str(&text).unwrap();
+
+        let mut merged = Replay::new(&World::with_seed(3, 1234), 100.0);
+        for frame in &replay.frames {
+            merged.push(frame.clone());
+        }
+        assert_eq!(merged.frames.len(), replay.frames.len() - 49);
+
+        let original = play(&replay);
+        let replayed = play(&loaded);
+        assert_eq!(original.level, 4);
+        assert_eq!(original.player_pos, replayed.player_pos);
+        assert_eq!(original.health, replayed.health);
+        assert_eq!(original.piss, replayed.piss);
+        assert_eq!(original.obstacles, replayed.obstacles);
+        assert_eq!(original.player_pos, play(&merged).player_pos);
+    }
+}
@@ -1,4 +1,7 @@
+pub mod clock;
 pub mod commands;
 pub mod draw;
+pub mod focus;
 pub mod gui;
+pub mod menu;
 pub mod models;
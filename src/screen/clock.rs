@@ -0,0 +1,12 @@
+use corporation_slayer::clock::Clock;
+use corporation_slayer::common::TimestampSeconds;
+use macroquad::miniquad::date::now;
+
+/// Wall-clock time, as reported by the platform.
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> TimestampSeconds {
+        now()
+    }
+}
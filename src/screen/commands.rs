@@ -1,33 +1,20 @@
-use crate::common::TimestampSeconds;
 use crate::screen::gui;
 use crate::screen::gui::move_button;
-use macroquad::miniquad::date::now;
+use corporation_slayer::clock::Clock;
+use corporation_slayer::commands::{Commands, Movement};
 use macroquad::prelude::*;
 
-pub struct Commands {
-    pub should_quit: bool,
-    pub forward_movement: Movement,
-    pub left_movement: Movement,
-    pub jump: bool,
-    pub ts_now: TimestampSeconds,
-    pub pissing: bool,
-}
-
-#[derive(PartialEq, Debug)]
-pub enum Movement {
-    None,
-    Positive,
-    Negative,
-}
-
-pub fn get_commands() -> Commands {
+/// The pause button only shows while `playing`, so clicks on the menus over it don't count.
+pub fn get_commands(clock: &impl Clock, playing: bool) -> Commands {
     let (forward_movement, left_movement) = get_forward_and_left_movement();
+    // polled anyway, so that a click held from a menu doesn't count as a new one afterwards
+    let pause_clicked = gui::pause_button::is_clicked();
     Commands {
-        should_quit: is_key_pressed(KeyCode::Escape),
+        pause: is_key_pressed(KeyCode::Escape) || (playing && pause_clicked),
         forward_movement,
         left_movement,
         jump: get_jump(),
-        ts_now: now(),
+        ts_now: clock.now(),
         pissing: get_pissing(),
     }
 }
@@ -92,18 +79,6 @@ fn get_cancellable_movement(positive: bool, negative: bool) -> Movement {
     }
 }
 
-impl Movement {
-    pub fn from_value(value: f32) -> Movement {
-        if value == 0.0 {
-            Movement::None
-        } else if value > 0.0 {
-            Movement::Positive
-        } else {
-            Movement::Negative
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
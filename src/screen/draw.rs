@@ -1,25 +1,52 @@
-use crate::common::TimestampSeconds;
 use crate::screen::gui::draw_gui;
+use crate::screen::menu::{difficulty_name, next_preset, Menu, Settings};
 use crate::screen::models::{Model, Models};
-use crate::world::obstacles::Obstacles;
-use crate::world::{World, PLAYER_HEIGHT};
+use corporation_slayer::clock::Clock;
+use corporation_slayer::common::TimestampSeconds;
+use corporation_slayer::game::{Choice, Game, GameState};
+use corporation_slayer::progress::Progress;
+use corporation_slayer::world::behaviour::Behaviour;
+use corporation_slayer::world::boss::BOSS_SIZE;
+use corporation_slayer::world::obstacles::{Obstacle, Obstacles};
+use corporation_slayer::world::projectiles::PROJECTILE_SIZE;
+use corporation_slayer::world::score::{Score, POINTS_PER_KILL};
+use corporation_slayer::world::{
+    get_random_seed, Mode, Transition, World, ENDLESS_VIEW_DISTANCE, PLAYER_SIZE,
+};
 use macroquad::models::Vertex;
 use macroquad::prelude::*;
-use macroquad::ui::root_ui;
-use macroquad::ui::widgets::{Button, Label, Window};
+use macroquad::ui::widgets::{Button, InputText, Label, Window};
+use macroquad::ui::{hash, root_ui, Ui};
 
 pub const FONT_SIZE: f32 = 16.0;
 const PISS_YELLOW: Color = Color::new(0.9, 0.9, 0.0, 1.0);
 const PLAYER_COLOR: Color = Color::new(0.00, 0.47, 0.95, 0.3);
+const HIT_FLASH_DURATION: TimestampSeconds = 0.1;
 
-pub struct Drawer {}
+pub struct Drawer {
+    seed_input: String,
+    settings: Settings,
+    menu: Menu,
+}
 
 impl Drawer {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(world: &World) -> Self {
+        Self {
+            seed_input: String::new(),
+            settings: Settings::new(world),
+            menu: Menu::new(),
+        }
     }
 
-    pub fn draw(&mut self, world: &mut World, models: &Models) {
+    /// Returns what the player picked in a menu or dialog, if anything.
+    pub fn draw(
+        &mut self,
+        game: &Game,
+        progress: &Progress,
+        models: &Models,
+        clock: &impl Clock,
+    ) -> Option<Choice> {
+        let world = &game.world;
         set_camera(&Camera3D {
             position: vec3(-3.0 + world.player_pos.x, 4.0, 0.0),
             up: vec3(0.0, 1.0, 0.0),
@@ -30,10 +57,23 @@ impl Drawer {
         // draw_grid(20, 1., BLACK, GRAY);
         draw_walls(world);
         draw_obstacles(&world.obstacles, world.previous_frame_ts);
+        draw_boss(world);
         self.draw_piss_spray(world);
         draw_player(world, &models.player);
+        set_default_camera();
         draw_hud(world);
+        let choice = match game.state {
+            GameState::Menu | GameState::Paused => {
+                return self.menu.draw(game, progress, &mut self.settings, clock);
+            }
+            GameState::LevelComplete => draw_level_finished(world),
+            GameState::GameOver => {
+                draw_game_over(world, &mut self.seed_input, &mut self.settings, clock)
+            }
+            GameState::Playing => None,
+        };
         draw_gui();
+        choice.map(Choice::Transition)
     }
 
     pub fn draw_piss_spray(&mut self, world: &World) {
@@ -50,26 +90,25 @@ impl Drawer {
 
 fn draw_walls(world: &World) {
     let starting_wall = 5.0;
-    let end_x = world
-        .obstacles
-        .last()
-        .unwrap()
-        .get_pos(world.previous_frame_ts)
-        .x
-        + 10.0
-        + starting_wall;
-    let v0 = Vec3::new(-5.0, 6.0, -2.0);
+    let (start_x, end_x) = match world.mode {
+        Mode::Levels => (-starting_wall, world.finish_x),
+        Mode::Endless => (
+            world.player_pos.x - starting_wall,
+            world.player_pos.x + ENDLESS_VIEW_DISTANCE,
+        ),
+    };
+    let v0 = Vec3::new(start_x, 6.0, -2.0);
     let v0v1 = Vec3::new(0.0, -6.0, 0.0);
-    let v0v3 = Vec3::new(end_x, 0.0, 0.0);
+    let v0v3 = Vec3::new(end_x - start_x, 0.0, 0.0);
     let corners = compute_plane_corners(v0, v0v1, v0v3);
     draw_mesh(&Mesh {
         texture: None,
         vertices: point_to_vertex_no_texture(corners, LIGHTGRAY),
         indices: vec![0, 1, 2, 0, 2, 3],
     });
-    let v0 = Vec3::new(-5.0, 6.0, 2.0);
+    let v0 = Vec3::new(start_x, 6.0, 2.0);
     let v0v1 = Vec3::new(0.0, -6.0, 0.0);
-    let v0v3 = Vec3::new(end_x, 0.0, 0.0);
+    let v0v3 = Vec3::new(end_x - start_x, 0.0, 0.0);
     let corners = compute_plane_corners(v0, v0v1, v0v3);
     draw_mesh(&Mesh {
         texture: None,
@@ -95,12 +134,7 @@ fn point_to_vertex_no_texture(points: Vec<Vec3>, color: Color) -> Vec<Vertex> {
 
 fn draw_player(world: &World, _model: &Model) {
     let color = if world.colliding { RED } else { PLAYER_COLOR };
-    draw_cube_from_floor(
-        world.player_pos,
-        Vec3::new(1.0, PLAYER_HEIGHT, 1.0),
-        None,
-        color,
-    );
+    draw_cube_from_floor(world.player_pos, PLAYER_SIZE, None, color);
     // push_camera_state();
     // set_camera(&Camera3D {
     //     position: vec3(-3.0 + world.player_pos.x, 4.0, 0.0) - world.player_pos,
@@ -123,18 +157,71 @@ pub fn draw_cube_from_floor(
 }
 
 pub fn draw_obstacles(obstacles: &Obstacles, ts: TimestampSeconds) {
-    let size = Vec3::new(0.8, 0.5, 0.8);
     for obstacle in obstacles {
-        draw_cube_from_floor(obstacle.get_pos(ts), size, None, obstacle.get_color());
+        let pos = obstacle.get_pos(ts);
+        let health = obstacle.hp() / obstacle.max_hp();
+        let size = obstacle.size();
+        draw_cube_from_floor(pos, size, None, hit_tint(obstacle, ts));
+        if obstacle.is_alive() && health < 1.0 {
+            draw_obstacle_health_bar(pos + Vec3::new(0.0, size.y + 0.3, 0.0), health);
+        }
     }
 }
 
-fn draw_hud(world: &mut World) {
-    set_default_camera();
+fn hit_tint(obstacle: &Obstacle, ts: TimestampSeconds) -> Color {
+    flash(obstacle_color(obstacle), obstacle.last_hit(), ts)
+}
+
+/// Flashes towards white right after each hit.
+fn flash(color: Color, last_hit: Option<TimestampSeconds>, ts: TimestampSeconds) -> Color {
+    let since_hit = last_hit.map_or(f64::MAX, |hit| ts - hit);
+    let flash = (1.0 - since_hit / HIT_FLASH_DURATION).clamp(0.0, 1.0) as f32;
+    Color::new(
+        color.r + (1.0 - color.r) * flash,
+        color.g + (1.0 - color.g) * flash,
+        color.b + (1.0 - color.b) * flash,
+        color.a,
+    )
+}
+
+fn draw_boss(world: &World) {
+    if let Some(boss) = world.boss.as_ref().filter(|boss| !boss.is_defeated()) {
+        let color = flash(DARKPURPLE, boss.last_hit(), world.previous_frame_ts);
+        draw_cube_from_floor(boss.pos, BOSS_SIZE, None, color);
+    }
+    for projectile in &world.projectiles {
+        draw_cube_from_floor(projectile.position, PROJECTILE_SIZE, None, RED);
+    }
+}
+
+fn draw_obstacle_health_bar(center: Vec3, health: f32) {
+    let width = 0.8;
+    let size = Vec3::new(0.05, 0.08, width);
+    draw_cube(center, size, None, DARKGRAY);
+    let filled = Vec3::new(size.x * 1.5, size.y, width * health);
+    let left_aligned = center - Vec3::new(0.0, 0.0, (width - filled.z) * 0.5);
+    let color = Color::new(1.0 - health, health, 0.0, 1.0);
+    draw_cube(left_aligned, filled, None, color);
+}
+
+fn obstacle_color(obstacle: &Obstacle) -> Color {
+    if !obstacle.is_alive() {
+        BROWN
+    } else if let Behaviour::Guard { .. } = obstacle.behaviour() {
+        DARKBLUE
+    } else if obstacle.weapon().is_some() {
+        MAROON
+    } else {
+        ORANGE
+    }
+}
+
+fn draw_hud(world: &World) {
     draw_health(world);
     draw_piss(world);
-    draw_level_finished(world);
-    draw_game_over(world);
+    draw_seed(world);
+    draw_score(world);
+    draw_boss_health(world);
 }
 
 fn draw_health(world: &World) {
@@ -153,6 +240,20 @@ fn draw_health(world: &World) {
     draw_text("HEALTH", padding, padding_text, FONT_SIZE, BLACK);
 }
 
+fn draw_boss_health(world: &World) {
+    if let Some(boss) = world.boss.as_ref().filter(|boss| !boss.is_defeated()) {
+        let full_width = screen_width();
+        let padding = 0.05 * full_width;
+        let width = full_width - 2.0 * padding;
+        let top = padding * 5.0;
+        let health = boss.hp() / boss.max_hp();
+        draw_rectangle(padding, top, width * health, padding, DARKPURPLE);
+        draw_rectangle_lines(padding, top, width, padding, 4.0, BLACK);
+        let text = format!("THE CORPORATION    PHASE {}/3", boss.phase() + 1);
+        draw_text(&text, padding, top + padding * 2.0, FONT_SIZE, BLACK);
+    }
+}
+
 fn draw_piss(world: &World) {
     let full_width = screen_width();
     let width = 0.4 * full_width;
@@ -191,50 +292,135 @@ fn draw_piss(world: &World) {
     );
 }
 
-fn draw_level_finished(world: &mut World) {
-    let end_x = world
-        .obstacles
-        .last()
-        .unwrap()
-        .get_pos(world.previous_frame_ts)
-        .x
-        + 10.0;
-    if world.player_pos.x > end_x {
-        let w = screen_width();
-        let h = screen_height();
-        Window::new(1, Vec2::new(w / 4.0, h / 4.0), Vec2::new(w / 2.0, h / 4.0))
-            .label(&format!("Level {} completed!", world.level + 1))
-            .ui(&mut root_ui(), |ui| {
-                if Button::new(format!("Continue to level {}", world.level + 2).as_str()).ui(ui)
-                    || is_key_down(KeyCode::Space)
-                    || is_key_down(KeyCode::Enter)
-                    || is_key_down(KeyCode::KpEnter)
-                {
-                    world.level += 1;
-                    world.piss = 1.0_f32.min(world.piss + 0.1);
-                    world.regenerate();
-                }
-            });
-    }
+fn draw_seed(world: &World) {
+    let full_width = screen_width();
+    let padding = 0.05 * full_width;
+    let text = match world.mode {
+        Mode::Levels => format!("LEVEL {}    SEED {}", world.level + 1, world.seed),
+        Mode::Endless => format!("{:.0} METERS    SEED {}", world.player_pos.x, world.seed),
+    };
+    draw_text(&text, padding, padding * 4.0, FONT_SIZE, BLACK);
+}
+
+/// Right-aligned under the piss bar, across from the level and seed.
+fn draw_score(world: &World) {
+    let full_width = screen_width();
+    let padding = 0.05 * full_width;
+    let text = format!("SCORE {}", world.score.total());
+    let text_length = measure_text(&text, None, FONT_SIZE as u16, 1.0);
+    draw_text(
+        &text,
+        full_width - padding - text_length.width,
+        padding * 4.0,
+        FONT_SIZE,
+        BLACK,
+    );
+}
+
+/// Points of the whole run so far, by source.
+fn score_breakdown(ui: &mut Ui, score: &Score) {
+    Label::new(format!(
+        "Kills: {} x {} = {}",
+        score.kills,
+        POINTS_PER_KILL,
+        score.kill_points()
+    ))
+    .ui(ui);
+    Label::new(format!(
+        "Distance: {:.0} meters = {}",
+        score.meters(),
+        score.distance_points()
+    ))
+    .ui(ui);
+    Label::new(format!("Time bonuses: {}", score.time_bonus)).ui(ui);
+    Label::new(format!("Health bonuses: {}", score.health_bonus)).ui(ui);
+    Label::new(format!("Score: {}", score.total())).ui(ui);
 }
 
-fn draw_game_over(world: &mut World) {
+fn draw_level_finished(world: &World) -> Option<Transition> {
+    let mut transition = None;
+    let w = screen_width();
+    let h = screen_height();
+    Window::new(1, Vec2::new(w / 4.0, h / 4.0), Vec2::new(w / 2.0, h * 0.4))
+        .label(&match world.boss {
+            Some(_) => "The corporation is defeated!".to_owned(),
+            None => format!("Level {} completed!", world.level + 1),
+        })
+        .ui(&mut root_ui(), |ui| {
+            if let Some(bonus) = world.score.last_level {
+                Label::new(format!(
+                    "In {:.1} seconds: +{}",
+                    bonus.time, bonus.time_bonus
+                ))
+                .ui(ui);
+                Label::new(format!(
+                    "With {:.0}% health: +{}",
+                    bonus.health * 100.0,
+                    bonus.health_bonus
+                ))
+                .ui(ui);
+            }
+            score_breakdown(ui, &world.score);
+            if Button::new(format!("Continue to level {}", world.level + 2).as_str()).ui(ui)
+                || is_key_down(KeyCode::Space)
+                || is_key_down(KeyCode::Enter)
+                || is_key_down(KeyCode::KpEnter)
+            {
+                transition = Some(Transition::NextLevel);
+            }
+        });
+    transition
+}
+
+fn draw_game_over(
+    world: &World,
+    seed_input: &mut String,
+    settings: &mut Settings,
+    clock: &impl Clock,
+) -> Option<Transition> {
+    let mut transition = None;
     if let Some(end) = world.game_end {
         let w = screen_width();
         let h = screen_height();
-        Window::new(1, Vec2::new(w / 4.0, h / 4.0), Vec2::new(w / 2.0, h / 4.0))
+        Window::new(1, Vec2::new(w / 4.0, h / 4.0), Vec2::new(w / 2.0, h * 0.6))
             .label("Game Over")
             .ui(&mut root_ui(), |ui| {
-                Label::new(format!("Reached level {}", world.level + 1)).ui(ui);
-                Label::new(format!("(and {:.2} meters)", world.player_pos.x)).ui(ui);
+                match world.mode {
+                    Mode::Levels => {
+                        Label::new(format!("Reached level {}", world.level + 1)).ui(ui);
+                        Label::new(format!("(and {:.2} meters)", world.player_pos.x)).ui(ui);
+                    }
+                    Mode::Endless => {
+                        Label::new(format!("Survived {:.2} meters", world.player_pos.x)).ui(ui);
+                    }
+                }
                 Label::new(format!("in {:.3} seconds", end - world.game_start)).ui(ui);
+                Label::new(format!("with seed {}", world.seed)).ui(ui);
+                score_breakdown(ui, &world.score);
+                InputText::new(hash!())
+                    .label("Next seed")
+                    .filter_numbers()
+                    .ui(ui, seed_input);
+                Label::new("(leave empty for a random seed)").ui(ui);
+                let name = difficulty_name(&settings.difficulty);
+                if Button::new(format!("Difficulty: {}", name)).ui(ui) {
+                    settings.difficulty = next_preset(&settings.difficulty);
+                }
                 if Button::new("Restart").ui(ui)
                     || is_key_down(KeyCode::Space)
                     || is_key_down(KeyCode::Enter)
                     || is_key_down(KeyCode::KpEnter)
                 {
-                    *world = World::new(0);
+                    let seed = match seed_input.trim().parse() {
+                        Ok(seed) => seed,
+                        Err(_) => get_random_seed(clock),
+                    };
+                    transition = Some(Transition::Restart {
+                        seed,
+                        difficulty: settings.difficulty,
+                    });
                 }
             });
     }
+    transition
 }
@@ -0,0 +1,24 @@
+//! Whether the player is looking at the game. Only browsers tell, through the plugin registered
+//! in `export_html/index.html`; a desktop window keeps drawing in the background anyway.
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn corporation_slayer_has_focus() -> bool;
+}
+
+/// Version of the JS plugin this expects, checked by the miniquad loader.
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn corporation_slayer_focus_crate_version() -> u32 {
+    1 << 16 // 0.1.0
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn has_focus() -> bool {
+    unsafe { corporation_slayer_has_focus() }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn has_focus() -> bool {
+    true
+}
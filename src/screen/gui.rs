@@ -3,7 +3,9 @@ use macroquad::prelude::{
     draw_circle, draw_rectangle, draw_text, measure_text, mouse_position_local, touches_local,
     Color, BLACK,
 };
-use macroquad::prelude::{is_mouse_button_down, screen_height, screen_width, MouseButton, Vec2};
+use macroquad::prelude::{
+    is_mouse_button_down, screen_height, screen_width, MouseButton, Rect, Vec2,
+};
 
 const PRESSED_BUTTON_COLOR: Color = Color::new(0.1, 0.1, 0.1, 0.3);
 const BUTTON_COLOR: Color = Color::new(0.2, 0.2, 0.2, 0.2);
@@ -12,6 +14,7 @@ pub fn draw_gui() {
     jump_button::draw();
     piss_button::draw();
     move_button::draw();
+    pause_button::draw();
 }
 
 pub mod jump_button {
@@ -22,7 +25,7 @@ pub mod jump_button {
     pub fn is_clicked() -> bool {
         let clicked = clicked_or_touched(inside_button);
         unsafe { LAST_IS_CLICKED = clicked };
-        return clicked;
+        clicked
     }
 
     fn inside_button(position: Vec2) -> bool {
@@ -42,7 +45,7 @@ pub mod piss_button {
     pub fn is_clicked() -> bool {
         let clicked = clicked_or_touched(inside_button);
         unsafe { LAST_IS_CLICKED = clicked };
-        return clicked;
+        clicked
     }
 
     fn inside_button(position: Vec2) -> bool {
@@ -62,7 +65,7 @@ pub mod move_button {
     pub fn get_movement() -> Option<Vec2> {
         let clicked = clicked_or_touched_pos(inside_button);
         unsafe { LAST_IS_CLICKED = clicked.is_some() };
-        return clicked.map(|screen_pos| {
+        clicked.map(|screen_pos| {
             let (x, y, radius) = get_circle_button_pos();
             let x_local = x / screen_width() * 2.0 - 1.0;
             let y_local = y / screen_height() * 2.0 - 1.0;
@@ -76,7 +79,7 @@ pub mod move_button {
                 x_local_button.clamp(-1.0, 1.0),
                 y_local_button.clamp(-1.0, 1.0),
             )
-        });
+        })
     }
 
     fn inside_button(position: Vec2) -> bool {
@@ -88,6 +91,51 @@ pub mod move_button {
     }
 }
 
+pub mod pause_button {
+    use super::*;
+
+    /// In the range of {x: [0, 1], y: [0, 1]}, below the HUD on the right.
+    const AREA: Rect = Rect {
+        x: 0.8,
+        y: 0.32,
+        w: 0.15,
+        h: 0.07,
+    };
+
+    static mut LAST_IS_CLICKED: bool = false;
+
+    /// Only true on the first frame of a click, so that holding it doesn't pause and resume.
+    pub fn is_clicked() -> bool {
+        let clicked = clicked_or_touched(inside_button);
+        let was_clicked = unsafe { LAST_IS_CLICKED };
+        unsafe { LAST_IS_CLICKED = clicked };
+        clicked && !was_clicked
+    }
+
+    fn inside_button(position: Vec2) -> bool {
+        AREA.contains((position + Vec2::ONE) * 0.5)
+    }
+
+    pub fn draw() {
+        let (w, h) = (screen_width(), screen_height());
+        let color = if unsafe { LAST_IS_CLICKED } {
+            PRESSED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        draw_rectangle(AREA.x * w, AREA.y * h, AREA.w * w, AREA.h * h, color);
+        let text = "PAUSE";
+        let size = measure_text(text, None, FONT_SIZE as u16, 1.0);
+        draw_text(
+            text,
+            (AREA.x + AREA.w * 0.5) * w - size.width * 0.5,
+            (AREA.y + AREA.h * 0.5) * h + size.height * 0.5,
+            FONT_SIZE,
+            BLACK,
+        );
+    }
+}
+
 /// inside_button() will receive screen coordinates in the range of {x: [-1, 1], y: [-1, 1]}
 fn clicked_or_touched<F: Fn(Vec2) -> bool>(inside_button: F) -> bool {
     clicked_or_touched_pos(inside_button).is_some()
@@ -103,7 +151,7 @@ fn clicked_or_touched_pos<F: Fn(Vec2) -> bool>(inside_button: F) -> Option<Vec2>
             return Some(touch.position);
         }
     }
-    return None;
+    None
 }
 
 /// position is in the range of {x: [0, 1], y: [0, 1]}
@@ -0,0 +1,238 @@
+//! Menus shown over the frozen world, the main menu and the pause menu, navigable with the mouse,
+//! touch, or the arrow keys and Enter.
+
+use corporation_slayer::clock::Clock;
+use corporation_slayer::game::{Choice, Game, GameState};
+use corporation_slayer::progress::Progress;
+use corporation_slayer::world::difficulty::{Difficulty, PRESET_NAMES};
+use corporation_slayer::world::{get_random_seed, Mode, Transition, World};
+use macroquad::prelude::*;
+use macroquad::ui::widgets::{Button, InputText, Label, Window};
+use macroquad::ui::{hash, root_ui, Ui};
+
+/// Choices kept for the next run.
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub mode: Mode,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Page {
+    Main,
+    Seed,
+    Settings,
+    Pause,
+}
+
+#[derive(Copy, Clone)]
+enum MainItem {
+    Play,
+    Continue,
+    PickLevel,
+    Seed,
+    Settings,
+    Quit,
+}
+
+pub struct Menu {
+    page: Page,
+    /// Button highlighted for the keyboard.
+    selected: usize,
+    seed_input: String,
+    /// Unlocked level to continue from, the highest one unless the player picked another.
+    picked_level: Option<i32>,
+}
+
+impl Settings {
+    pub fn new(world: &World) -> Self {
+        Self {
+            difficulty: world.difficulty,
+            mode: world.mode,
+        }
+    }
+
+    /// A run with these settings.
+    fn start(&self, level: i32, seed: u64) -> Choice {
+        Choice::Transition(Transition::Start {
+            level,
+            seed,
+            difficulty: self.difficulty,
+            mode: self.mode,
+        })
+    }
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self {
+            page: Page::Main,
+            selected: 0,
+            seed_input: String::new(),
+            picked_level: None,
+        }
+    }
+
+    /// Draws the main menu, or the pause menu while paused. Returns what the player picked, if
+    /// anything.
+    pub fn draw(
+        &mut self,
+        game: &Game,
+        progress: &Progress,
+        settings: &mut Settings,
+        clock: &impl Clock,
+    ) -> Option<Choice> {
+        let world = &game.world;
+        let root = match game.state {
+            GameState::Paused => Page::Pause,
+            _ => Page::Main,
+        };
+        if self.page == Page::Main || self.page == Page::Pause {
+            self.page = root;
+        }
+        let mut choice = None;
+        let mut page = self.page;
+        let w = screen_width();
+        let h = screen_height();
+        Window::new(
+            hash!(),
+            Vec2::new(w * 0.1, h * 0.2),
+            Vec2::new(w * 0.8, h * 0.5),
+        )
+        .label(match root {
+            Page::Pause => "Paused",
+            _ => "Corporation slayer",
+        })
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut root_ui(), |ui| match self.page {
+            Page::Main => {
+                let highest_level = progress.highest_level.max(0);
+                let picked_level = self
+                    .picked_level
+                    .unwrap_or(highest_level)
+                    .min(highest_level);
+                let mut items = vec![(MainItem::Play, "Play".to_owned())];
+                if settings.mode == Mode::Levels && highest_level > 0 {
+                    let label = format!("Continue from level {}", picked_level + 1);
+                    items.push((MainItem::Continue, label));
+                    let label = format!("Level: {} of {}", picked_level + 1, highest_level + 1);
+                    items.push((MainItem::PickLevel, label));
+                }
+                items.push((MainItem::Seed, "Enter seed".to_owned()));
+                items.push((MainItem::Settings, "Settings".to_owned()));
+                // a browser tab has nothing to quit to
+                if cfg!(not(target_arch = "wasm32")) {
+                    items.push((MainItem::Quit, "Quit".to_owned()));
+                }
+                let labels: Vec<&str> = items.iter().map(|(_, label)| label.as_str()).collect();
+                match menu_buttons(ui, &labels, &mut self.selected).map(|i| items[i].0) {
+                    Some(MainItem::Play) => {
+                        choice = Some(settings.start(world.level, world.seed));
+                    }
+                    Some(MainItem::Continue) => {
+                        let seed = get_random_seed(clock);
+                        choice = Some(settings.start(picked_level, seed));
+                    }
+                    Some(MainItem::PickLevel) => {
+                        self.picked_level = Some((picked_level + 1) % (highest_level + 1));
+                    }
+                    Some(MainItem::Seed) => page = Page::Seed,
+                    Some(MainItem::Settings) => page = Page::Settings,
+                    Some(MainItem::Quit) => choice = Some(Choice::Quit),
+                    None => {}
+                }
+            }
+            Page::Seed => {
+                InputText::new(hash!())
+                    .label("Seed")
+                    .filter_numbers()
+                    .ui(ui, &mut self.seed_input);
+                Label::new("(leave empty for a random seed)").ui(ui);
+                match menu_buttons(ui, &["Start", "Back"], &mut self.selected) {
+                    Some(0) => {
+                        let seed = match self.seed_input.trim().parse() {
+                            Ok(seed) => seed,
+                            Err(_) => get_random_seed(clock),
+                        };
+                        choice = Some(settings.start(0, seed));
+                    }
+                    Some(_) => page = Page::Main,
+                    None => {}
+                }
+            }
+            Page::Pause => {
+                let labels = ["Resume", "Restart level", "Settings", "Quit to menu"];
+                match menu_buttons(ui, &labels, &mut self.selected) {
+                    Some(0) => choice = Some(Choice::Resume),
+                    Some(1) => choice = Some(Choice::Transition(Transition::RestartLevel)),
+                    Some(2) => page = Page::Settings,
+                    Some(_) => choice = Some(Choice::MainMenu),
+                    None => {}
+                }
+            }
+            Page::Settings => {
+                let difficulty = format!("Difficulty: {}", difficulty_name(&settings.difficulty));
+                let mode = match settings.mode {
+                    Mode::Levels => "Mode: levels",
+                    Mode::Endless => "Mode: endless",
+                };
+                match menu_buttons(ui, &[&difficulty, mode, "Back"], &mut self.selected) {
+                    Some(0) => settings.difficulty = next_preset(&settings.difficulty),
+                    Some(1) => {
+                        settings.mode = match settings.mode {
+                            Mode::Levels => Mode::Endless,
+                            Mode::Endless => Mode::Levels,
+                        }
+                    }
+                    Some(_) => page = root,
+                    None => {}
+                }
+                Label::new("(applies to the next run)").ui(ui);
+            }
+        });
+        if page != self.page {
+            self.page = page;
+            self.selected = 0;
+        }
+        choice
+    }
+}
+
+/// Draws a column of buttons, highlighting the selected one, which the arrow keys move. Returns
+/// the index of the button clicked, or the selected one if Enter was pressed.
+fn menu_buttons(ui: &mut Ui, labels: &[&str], selected: &mut usize) -> Option<usize> {
+    if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+        *selected = (*selected + 1) % labels.len();
+    }
+    if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+        *selected = (*selected + labels.len() - 1) % labels.len();
+    }
+    *selected = (*selected).min(labels.len() - 1);
+    let mut clicked = None;
+    for (i, label) in labels.iter().enumerate() {
+        if Button::new(*label).selected(i == *selected).ui(ui) {
+            clicked = Some(i);
+        }
+    }
+    let pressed = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter);
+    clicked.or_else(|| pressed.then_some(*selected))
+}
+
+pub fn difficulty_name(difficulty: &Difficulty) -> &'static str {
+    difficulty.preset_name().unwrap_or("custom")
+}
+
+/// Cycles through the presets, starting over from the first one after a custom difficulty.
+pub fn next_preset(difficulty: &Difficulty) -> Difficulty {
+    let next_index = match difficulty.preset_name() {
+        Some(name) => {
+            PRESET_NAMES
+                .iter()
+                .position(|preset| *preset == name)
+                .unwrap()
+                + 1
+        }
+        None => 0,
+    };
+    Difficulty::preset(PRESET_NAMES[next_index % PRESET_NAMES.len()]).unwrap()
+}
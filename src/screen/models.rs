@@ -1,4 +1,4 @@
-use crate::common::AnyError;
+use corporation_slayer::common::AnyError;
 // use gltf::Semantic;
 // use macroquad::models::Vertex;
 use macroquad::prelude::Mesh;
@@ -23,7 +23,7 @@ pub fn load_models() -> Result<Models, AnyError> {
 }
 
 fn gltf_to_meshes(_path: &str) -> Result<Vec<Mesh>, AnyError> {
-    return Ok(Vec::new());
+    Ok(Vec::new())
     // let (document, buffers, _images) = gltf::import(path)?;
     // let mut meshes = Vec::<Mesh>::new();
     // for mesh in document.meshes() {
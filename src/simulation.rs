@@ -0,0 +1,130 @@
+use crate::bot::Bot;
+use crate::common::TimestampSeconds;
+use crate::game::Game;
+use crate::replay::Replay;
+use crate::world::{Event, Transition, World};
+use serde::Serialize;
+
+/// Outcome of a headless run.
+#[derive(Serialize, PartialEq, Debug)]
+pub struct Summary {
+    pub seed: u64,
+    pub starting_level: i32,
+    pub levels_cleared: i32,
+    /// Meters covered over all the levels of the run.
+    pub distance: f32,
+    pub time: TimestampSeconds,
+    pub health: f32,
+    pub piss_used: f32,
+    pub obstacles_killed: u32,
+    pub score: u32,
+    pub game_over: bool,
+}
+
+/// Lets the bot play until it clears `levels` levels, dies, or spends `max_time` seconds.
+pub fn simulate_bot(
+    mut world: World,
+    levels: i32,
+    max_time: TimestampSeconds,
+    bot: &mut dyn Bot,
+) -> Summary {
+    let seed = world.seed;
+    let level = world.level;
+    let mut levels_cleared = 0;
+    while world.game_end.is_none()
+        && levels_cleared < levels
+        && world.now_ts - world.game_start < max_time
+    {
+        let commands = bot.commands(&world);
+        if world.update(&commands) == Some(Event::LevelFinished) {
+            levels_cleared += 1;
+            if levels_cleared < levels {
+                world.apply(Transition::NextLevel);
+            }
+        }
+    }
+    summarize(&world, seed, level, levels_cleared)
+}
+
+/// Plays back a recorded run and reports how it ended.
+pub fn simulate_replay(replay: &Replay) -> Summary {
+    let mut game = Game::new(replay.new_world(), replay.start_ts);
+    let mut seed = replay.seed;
+    let mut starting_level = replay.level;
+    let mut levels_cleared = 0;
+    for frame in &replay.frames {
+        game.play_back(frame);
+        if let Some(transition) = frame.transition {
+            match transition {
+                Transition::NextLevel => levels_cleared += 1,
+                Transition::RestartLevel => {}
+                Transition::Restart { seed: new_seed, .. } => {
+                    seed = new_seed;
+                    starting_level = 0;
+                    levels_cleared = 0;
+                }
+                Transition::Start {
+                    level,
+                    seed: new_seed,
+                    ..
+                } => {
+                    seed = new_seed;
+                    starting_level = level;
+                    levels_cleared = 0;
+                }
+            }
+            game.apply(transition);
+        }
+    }
+    summarize(&game.world, seed, starting_level, levels_cleared)
+}
+
+fn summarize(world: &World, seed: u64, starting_level: i32, levels_cleared: i32) -> Summary {
+    Summary {
+        seed,
+        starting_level,
+        levels_cleared,
+        distance: world.score.meters(),
+        time: world.game_end.unwrap_or(world.now_ts) - world.game_start,
+        health: world.health,
+        piss_used: world.piss_used,
+        obstacles_killed: world.score.kills,
+        score: world.score.total(),
+        game_over: world.game_end.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{ForwardBot, GreedyBot};
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        let first = simulate_bot(World::with_seed(2, 1234), 3, 120.0, &mut GreedyBot);
+        let second = simulate_bot(World::with_seed(2, 1234), 3, 120.0, &mut GreedyBot);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_distance_adds_up_across_levels() {
+        let world = World::with_seed(0, 1234);
+        let first_level_length = world.finish_x;
+        let summary = simulate_bot(world, 2, 120.0, &mut GreedyBot);
+        assert_eq!(summary.levels_cleared, 2);
+        assert!(summary.distance > first_level_length * 1.5);
+    }
+
+    #[test]
+    fn test_greedy_bot_beats_forward_bot() {
+        let mut greedy_cleared = 0;
+        let mut forward_cleared = 0;
+        for seed in 0..20 {
+            greedy_cleared +=
+                simulate_bot(World::with_seed(0, seed), 5, 120.0, &mut GreedyBot).levels_cleared;
+            forward_cleared +=
+                simulate_bot(World::with_seed(0, seed), 5, 120.0, &mut ForwardBot).levels_cleared;
+        }
+        assert!(greedy_cleared > forward_cleared);
+    }
+}
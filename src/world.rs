@@ -1,23 +1,74 @@
+pub mod ascii;
+pub mod behaviour;
+pub mod boss;
+pub mod difficulty;
+pub mod level;
 pub mod obstacles;
+pub mod patterns;
+pub mod projectiles;
+pub mod rng;
+pub mod score;
+pub mod solver;
+pub mod timestep;
 
+use crate::clock::Clock;
+use crate::commands::{Commands, Movement};
 use crate::common::TimestampSeconds;
-use crate::screen::commands::{Commands, Movement};
-use crate::world::obstacles::{generate_obstacles, Obstacles};
-use macroquad::miniquad::date::now;
-use macroquad::prelude::*;
+use crate::world::behaviour::Behaviour;
+use crate::world::boss::Boss;
+use crate::world::difficulty::Difficulty;
+use crate::world::level::{Level, LevelDescription};
+use crate::world::obstacles::{Obstacle, Obstacles};
+use crate::world::projectiles::Projectile;
+use crate::world::rng::Rng;
+use crate::world::score::Score;
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::f32::consts::SQRT_2;
 
-const SPEED: f32 = 5.0;
-const TUNNEL_HALF_WIDTH: f32 = 1.5;
-const JUMP_DURATION: f64 = 0.7;
+pub(crate) const SPEED: f32 = 5.0;
+pub const TUNNEL_HALF_WIDTH: f32 = 1.5;
+pub(crate) const JUMP_DURATION: f64 = 0.7;
 const PISS_DURATION: f64 = 0.3;
+const PISS_DRAIN_SPEED: f32 = 0.3;
+const PISS_HORIZONTAL_ACCELERATION: f64 = 60.0;
+/// Hit points each piss particle takes from the first obstacle it touches.
+const PISS_DAMAGE: f32 = 1.0;
+/// Damage of a full piss bar, all of it landing, since each tick of pissing fires one particle.
+pub(crate) const FULL_BLADDER_DAMAGE: f32 = PISS_DAMAGE / PISS_DRAIN_SPEED / TICK_DURATION as f32;
+/// Health lost to each projectile that hits the player.
+const PROJECTILE_DAMAGE: f32 = 0.15;
+/// Armed obstacles only fire when the player is at most this far before them.
+const FIRING_RANGE: f32 = 15.0;
 pub const PLAYER_HEIGHT: f32 = 1.75;
+/// Size of the box the player fills, from its feet up.
+pub const PLAYER_SIZE: Vec3 = Vec3::new(1.0, PLAYER_HEIGHT, 1.0);
+pub const PARTICLE_SIZE: Vec3 = Vec3::splat(0.1);
+
+/// Finishing a level in this many times the time it takes to run straight through it still earns
+/// a time bonus.
+const PAR_TIME_FACTOR: f64 = 2.5;
+/// Par time of boss fights, which are not about running.
+const BOSS_PAR_TIME: TimestampSeconds = 60.0;
+
+/// How far ahead of the player endless runs keep obstacles ready.
+pub const ENDLESS_VIEW_DISTANCE: f32 = 40.0;
+/// Obstacles further than this behind the player are dropped in endless runs.
+const ENDLESS_DISCARD_DISTANCE: f32 = 5.0;
+/// Distance after which endless runs get as hard as the next level.
+const ENDLESS_LEVEL_DISTANCE: f32 = 25.0;
+
+/// The simulation always advances in steps of this duration, regardless of the frame rate.
+pub const TICK_DURATION: TimestampSeconds = 1.0 / 120.0;
 
 pub struct World {
     pub player_pos: Vec3,
     pub jump_started: TimestampSeconds,
     pub obstacles: Obstacles,
+    pub boss: Option<Boss>,
+    pub projectiles: Vec<Projectile>,
+    pub finish_x: f32,
     pub previous_frame_ts: TimestampSeconds,
     pub now_ts: TimestampSeconds,
     pub colliding: bool,
@@ -25,57 +76,325 @@ pub struct World {
     pub health: f32,
     pub piss: f32,
     pub level: i32,
+    /// Level number the authored level is played as, if there is one.
+    authored_level_number: i32,
+    /// Seed of the current level's layout.
+    pub seed: u64,
+    /// Draws the seeds of the following levels, so a whole run is reproducible from its first seed.
+    rng: Rng,
+    /// Hand-authored level to start with, and again on every restart.
+    pub authored_level: Option<LevelDescription>,
+    pub difficulty: Difficulty,
+    pub mode: Mode,
+    /// Where the next chunk of obstacles starts in endless runs.
+    next_chunk_x: f32,
     pub game_start: TimestampSeconds,
     pub game_end: Option<TimestampSeconds>,
     pub piss_particles: VecDeque<Particle>,
+    pub piss_used: f32,
+    pub score: Score,
+    /// What the player had when the current level started, to go back to when restarting it.
+    checkpoint: Checkpoint,
+    pub level_start: TimestampSeconds,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Courses with a finish line, getting harder with each level.
+    #[default]
+    Levels,
+    /// A single course that never ends, getting harder with the distance.
+    Endless,
+}
+
+/// Changes of level that happen between ticks, e.g. chosen by the player in a dialog.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum Transition {
+    NextLevel,
+    Restart {
+        seed: u64,
+        #[serde(default)]
+        difficulty: Difficulty,
+    },
+    /// Plays the current level again from its start, or the whole course in endless runs.
+    RestartLevel,
+    /// Starts a new run from any level, e.g. chosen in the main menu.
+    Start {
+        level: i32,
+        seed: u64,
+        difficulty: Difficulty,
+        mode: Mode,
+    },
+}
+
+/// Something that happened during a tick, for the game to react to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Event {
+    LevelFinished,
+    GameOver,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Checkpoint {
+    health: f32,
+    piss: f32,
+    piss_used: f32,
+    score: Score,
 }
 
 pub struct Particle {
     pub position: Vec3,
     started: TimestampSeconds,
+    /// Already splashed on an obstacle, so it can't hurt another one.
+    spent: bool,
 }
 
 impl World {
-    pub fn new(level: i32) -> Self {
-        let mut world = Self {
+    pub fn new(level: i32, clock: &impl Clock) -> Self {
+        Self::with_seed(level, get_random_seed(clock))
+    }
+
+    pub fn with_seed(level: i32, seed: u64) -> Self {
+        Self::with_authored_level(level, seed, None)
+    }
+
+    /// Starts with the given authored level if any, continuing with generated levels after it.
+    pub fn with_authored_level(
+        level: i32,
+        seed: u64,
+        authored_level: Option<LevelDescription>,
+    ) -> Self {
+        Self::with_difficulty(level, seed, authored_level, Difficulty::default())
+    }
+
+    /// Like `with_authored_level`, generating the levels with the given difficulty.
+    pub fn with_difficulty(
+        level: i32,
+        seed: u64,
+        authored_level: Option<LevelDescription>,
+        difficulty: Difficulty,
+    ) -> Self {
+        let mut world =
+            Self::without_obstacles(level, seed, authored_level, difficulty, Mode::Levels);
+        match &world.authored_level {
+            Some(description) => world.start_level(description.to_level()),
+            None => world.regenerate(),
+        }
+        world
+    }
+
+    /// Obstacles keep coming, getting harder with the distance, until the player dies.
+    pub fn endless(seed: u64, difficulty: Difficulty) -> Self {
+        let mut world = Self::without_obstacles(0, seed, None, difficulty, Mode::Endless);
+        world.finish_x = f32::INFINITY;
+        world.stream_obstacles();
+        world
+    }
+
+    fn without_obstacles(
+        level: i32,
+        seed: u64,
+        authored_level: Option<LevelDescription>,
+        difficulty: Difficulty,
+        mode: Mode,
+    ) -> Self {
+        Self {
             health: 1.0,
             piss: 0.3,
             player_pos: Vec3::new(0.0, 0.0, 0.0),
-            jump_started: 0.0,
-            obstacles: Vec::new(),
-            previous_frame_ts: now(),
-            now_ts: now(),
+            jump_started: -JUMP_DURATION,
+            obstacles: Obstacles::new(),
+            boss: None,
+            projectiles: Vec::new(),
+            finish_x: 0.0,
+            previous_frame_ts: 0.0,
+            now_ts: 0.0,
             colliding: false,
             pissing: false,
             level,
-            game_start: now(),
+            authored_level_number: level,
+            seed,
+            rng: Rng::new(seed),
+            authored_level,
+            difficulty,
+            mode,
+            next_chunk_x: 0.0,
+            game_start: 0.0,
             game_end: None,
             piss_particles: VecDeque::new(),
-        };
-        world.regenerate();
-        world
+            piss_used: 0.0,
+            score: Score::default(),
+            checkpoint: Checkpoint::default(),
+            level_start: 0.0,
+        }
     }
 
-    pub fn update(&mut self, commands: Commands) {
-        if self.health > 0.0 {
-            self.update_time(&commands);
-            self.update_player_position(&commands);
-            self.update_jumped(&commands);
-            self.update_collision();
-            self.update_health(&commands);
-            self.update_pissing(&commands);
+    /// Advances the simulation by exactly one tick of `TICK_DURATION`. Returns what happened
+    /// during the tick, if anything the game should react to.
+    pub fn update(&mut self, commands: &Commands) -> Option<Event> {
+        if self.health <= 0.0 {
+            return None;
+        }
+        let was_finished = self.is_level_finished();
+        self.update_time();
+        self.update_player_position(commands);
+        if self.mode == Mode::Endless {
+            self.stream_obstacles();
+        }
+        self.update_obstacles();
+        self.update_projectiles();
+        self.update_jumped(commands);
+        self.update_collision();
+        self.update_health();
+        self.update_pissing(commands);
+        if !was_finished {
+            self.score.reach(self.player_pos.x.min(self.finish_x));
+        }
+        if self.game_end.is_some() {
+            Some(Event::GameOver)
+        } else if !was_finished && self.is_level_finished() {
+            let time = self.now_ts - self.level_start;
+            self.score.finish_level(time, self.par_time(), self.health);
+            Some(Event::LevelFinished)
+        } else {
+            None
         }
     }
 
+    pub fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::NextLevel => self.next_level(),
+            Transition::RestartLevel => {
+                self.restore(self.checkpoint);
+                match (self.mode, &self.authored_level) {
+                    (Mode::Endless, _) => self.replace(World::endless(self.seed, self.difficulty)),
+                    (Mode::Levels, Some(description))
+                        if self.level == self.authored_level_number =>
+                    {
+                        self.start_level(description.to_level())
+                    }
+                    (Mode::Levels, _) => self.regenerate(),
+                }
+            }
+            Transition::Restart { seed, difficulty } => self.apply(Transition::Start {
+                level: 0,
+                seed,
+                difficulty,
+                mode: self.mode,
+            }),
+            Transition::Start {
+                level,
+                seed,
+                difficulty,
+                mode,
+            } => {
+                let world = match mode {
+                    Mode::Levels if level == self.authored_level_number => {
+                        World::with_difficulty(level, seed, self.authored_level.clone(), difficulty)
+                    }
+                    Mode::Levels => World::with_difficulty(level, seed, None, difficulty),
+                    Mode::Endless => World::endless(seed, difficulty),
+                };
+                self.replace(world);
+            }
+        }
+    }
+
+    /// Switches to another run, keeping the authored level for when a run starts at its level
+    /// again.
+    fn replace(&mut self, mut world: World) {
+        world.authored_level = self.authored_level.take();
+        world.authored_level_number = self.authored_level_number;
+        *self = world;
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.health = checkpoint.health;
+        self.piss = checkpoint.piss;
+        self.piss_used = checkpoint.piss_used;
+        self.score = checkpoint.score;
+    }
+
+    pub fn next_level(&mut self) {
+        self.level += 1;
+        self.piss = 1.0_f32.min(self.piss + 0.1);
+        self.seed = self.rng.rand() as u64;
+        self.regenerate();
+    }
+
     pub fn regenerate(&mut self) {
-        self.obstacles = generate_obstacles(self.level, get_random_seed());
+        let level = match self.difficulty.boss_number(self.level) {
+            Some(boss_number) => Level::boss(boss_number),
+            None => Level::generate(self.level, self.seed, &self.difficulty),
+        };
+        self.start_level(level);
+    }
+
+    pub fn start_level(&mut self, level: Level) {
+        self.obstacles = level.obstacles;
+        self.boss = level.boss;
+        self.projectiles.clear();
+        self.finish_x = level.finish_x;
+        if let Some(health) = level.health {
+            self.health = health;
+        }
+        if let Some(piss) = level.piss {
+            self.piss = piss;
+        }
         self.player_pos = Vec3::new(0.0, 0.0, 0.0);
-        self.jump_started = 0.0;
+        self.jump_started = self.now_ts - JUMP_DURATION;
         self.piss_particles = VecDeque::new();
+        self.level_start = self.now_ts;
+        self.checkpoint = Checkpoint {
+            health: self.health,
+            piss: self.piss,
+            piss_used: self.piss_used,
+            score: self.score,
+        };
+    }
+
+    pub fn is_level_finished(&self) -> bool {
+        match &self.boss {
+            Some(boss) => boss.is_defeated(),
+            None => self.player_pos.x > self.finish_x,
+        }
+    }
+
+    /// Time to finish the current level in for a time bonus.
+    fn par_time(&self) -> TimestampSeconds {
+        match self.boss {
+            Some(_) => BOSS_PAR_TIME,
+            None => self.finish_x as f64 / SPEED as f64 * PAR_TIME_FACTOR,
+        }
+    }
+
+    /// Generates obstacles in chunks ahead of the player, and drops the ones left behind.
+    fn stream_obstacles(&mut self) {
+        while self.next_chunk_x < self.player_pos.x + ENDLESS_VIEW_DISTANCE {
+            // each chunk is carved as if it were a level of its own
+            let level = (self.next_chunk_x / ENDLESS_LEVEL_DISTANCE) as i32;
+            let chunk = Level::generate(level, self.rng.rand() as u64, &self.difficulty);
+            let chunk_length = chunk
+                .obstacles
+                .back()
+                .map_or(0.0, |obstacle| obstacle.origin().x)
+                .max(1.0);
+            for obstacle in chunk.obstacles {
+                self.obstacles
+                    .push_back(obstacle.shifted(self.next_chunk_x));
+            }
+            self.next_chunk_x += chunk_length;
+        }
+        // guards chase the player away from where they started, so look at where they are now
+        let discard_x = self.player_pos.x - ENDLESS_DISCARD_DISTANCE;
+        let now_ts = self.now_ts;
+        self.obstacles
+            .retain(|obstacle| obstacle.get_pos(now_ts).x >= discard_x);
     }
 
     fn update_player_position(&mut self, commands: &Commands) {
-        let dt = (commands.ts_now - self.previous_frame_ts) as f32;
+        let dt = (self.now_ts - self.previous_frame_ts) as f32;
         let mut dz = match commands.left_movement {
             Movement::None => 0.0,
             Movement::Positive => -SPEED * dt,
@@ -87,53 +406,102 @@ impl World {
             Movement::Negative => -SPEED * dt,
         };
         if dx != 0.0 && dz != 0.0 {
-            dx = dx / SQRT_2;
-            dz = dz / SQRT_2;
+            dx /= SQRT_2;
+            dz /= SQRT_2;
         }
         self.player_pos.x = (self.player_pos.x + dx).max(0.0);
         self.player_pos.z = (self.player_pos.z + dz).clamp(-TUNNEL_HALF_WIDTH, TUNNEL_HALF_WIDTH);
     }
 
     fn update_jumped(&mut self, commands: &Commands) {
-        let jump_time = commands.ts_now - self.jump_started;
+        let jump_time = self.now_ts - self.jump_started;
         let jumping = jump_time < JUMP_DURATION;
         if commands.jump && !jumping {
             self.jump_started = self.now_ts;
         }
         if jumping {
-            let height: f64 = 1.5;
-            let offset = JUMP_DURATION * 0.5;
-            // let jump_speed = (height/offset).sqrt();
-            let jump_speed = 1.0;
-            let height_coef = height / (offset * offset);
-            let x = jump_time * jump_speed - offset;
-            let y = height_coef * x * x;
-            self.player_pos.y = (height - y) as f32;
+            self.player_pos.y = jump_height(jump_time);
         } else {
             self.player_pos.y = 0.0
         }
     }
 
+    fn update_obstacles(&mut self) {
+        let dt = self.now_ts - self.previous_frame_ts;
+        let static_positions: Vec<Vec3> = self
+            .obstacles
+            .iter()
+            // dead obstacles turn static, but guards walk over them
+            .filter(|obstacle| obstacle.is_alive() && *obstacle.behaviour() == Behaviour::Static)
+            .map(Obstacle::origin)
+            .collect();
+        for obstacle in &mut self.obstacles {
+            if obstacle.is_alive() {
+                obstacle.update(dt, self.player_pos, &static_positions);
+            }
+        }
+    }
+
+    fn update_projectiles(&mut self) {
+        if let Some(boss) = &mut self.boss {
+            boss.update(self.now_ts, &mut self.projectiles);
+        }
+        for obstacle in &self.obstacles {
+            let pos = obstacle.get_pos(self.now_ts);
+            let ahead = pos.x - self.player_pos.x;
+            if !obstacle.is_alive() || !(-1.0..FIRING_RANGE).contains(&ahead) {
+                continue;
+            }
+            if let Some(weapon) = obstacle.weapon() {
+                self.projectiles
+                    .extend(weapon.fire(pos, self.previous_frame_ts, self.now_ts));
+            }
+        }
+        let dt = self.now_ts - self.previous_frame_ts;
+        for projectile in &mut self.projectiles {
+            projectile.update(dt);
+        }
+        let behind_x = self.player_pos.x - ENDLESS_DISCARD_DISTANCE;
+        self.projectiles.retain(|projectile| {
+            projectile.position.x > behind_x && !projectile.is_out_of_tunnel()
+        });
+    }
+
     fn update_collision(&mut self) {
+        let player_pos = self.player_pos;
+        let projectiles_before = self.projectiles.len();
+        self.projectiles
+            .retain(|projectile| !projectile.hits(player_pos, PLAYER_SIZE));
+        let projectile_hits = projectiles_before - self.projectiles.len();
+        if projectile_hits > 0 {
+            self.health -= PROJECTILE_DAMAGE * projectile_hits as f32;
+            self.health = self.health.max(0.0);
+            self.colliding = true;
+            return;
+        }
+        if let Some(boss) = &self.boss {
+            if boss.touches(player_pos, PLAYER_SIZE) {
+                self.colliding = true;
+                return;
+            }
+        }
         for obstacle in &self.obstacles {
-            if obstacle.is_alive() {
-                if collides(self.player_pos, obstacle.get_pos(self.now_ts)) {
-                    self.colliding = true;
-                    return;
-                }
+            if obstacle.is_alive() && obstacle.collides(player_pos, PLAYER_SIZE, self.now_ts) {
+                self.colliding = true;
+                return;
             }
         }
         self.colliding = false;
     }
 
-    fn update_health(&mut self, commands: &Commands) {
+    fn update_health(&mut self) {
         let pain_speed = 1.0;
         if self.colliding {
-            let dt = commands.ts_now - self.previous_frame_ts;
+            let dt = self.now_ts - self.previous_frame_ts;
             self.health -= (dt * pain_speed) as f32;
             self.health = self.health.clamp(0.0, 1.0);
             if self.health == 0.0 {
-                self.game_end = Some(commands.ts_now);
+                self.game_end = Some(self.now_ts);
             }
         }
     }
@@ -147,8 +515,11 @@ impl World {
         };
 
         // reduce piss bar
+        let dt = self.now_ts - self.previous_frame_ts;
         if self.pissing {
-            self.piss = 0.0_f32.max(self.piss - 0.005);
+            let piss_left = 0.0_f32.max(self.piss - PISS_DRAIN_SPEED * dt as f32);
+            self.piss_used += self.piss - piss_left;
+            self.piss = piss_left;
         }
 
         // add piss particle
@@ -156,13 +527,14 @@ impl World {
             self.piss_particles.push_back(Particle {
                 position: self.player_pos,
                 started: self.now_ts,
+                spent: false,
             });
         }
 
         // move piss particles
         let mut particles_to_remove = 0;
         for particle in &mut self.piss_particles {
-            let jump_time = commands.ts_now - particle.started;
+            let jump_time = self.now_ts - particle.started;
             let jumping = jump_time < PISS_DURATION;
             if jumping {
                 let height: f64 = 1.5;
@@ -172,12 +544,35 @@ impl World {
                 let x = jump_time * jump_speed - offset;
                 let y = height_coef * x * x;
                 particle.position.y = (height - y) as f32;
-                particle.position.x += (jump_time * jump_speed) as f32;
+                particle.position.x += (jump_time * PISS_HORIZONTAL_ACCELERATION * dt) as f32;
 
-                for obstacle in &mut self.obstacles {
-                    if collides(particle.position, obstacle.get_pos(self.now_ts)) {
-                        obstacle.kill(self.now_ts);
-                        break;
+                if !particle.spent {
+                    let cancelled = self
+                        .projectiles
+                        .iter()
+                        .position(|projectile| projectile.hits(particle.position, PARTICLE_SIZE));
+                    if let Some(i_projectile) = cancelled {
+                        self.projectiles.swap_remove(i_projectile);
+                        particle.spent = true;
+                    }
+                }
+                if let Some(boss) = &mut self.boss {
+                    if !particle.spent && boss.contains(particle.position) {
+                        particle.spent = true;
+                        boss.hit(PISS_DAMAGE, self.now_ts);
+                    }
+                }
+                if !particle.spent {
+                    for obstacle in &mut self.obstacles {
+                        if obstacle.is_alive()
+                            && obstacle.collides(particle.position, PARTICLE_SIZE, self.now_ts)
+                        {
+                            particle.spent = true;
+                            if obstacle.hit(PISS_DAMAGE, self.now_ts) {
+                                self.score.kill();
+                            }
+                            break;
+                        }
                     }
                 }
             } else {
@@ -191,22 +586,300 @@ impl World {
         }
     }
 
-    fn update_time(&mut self, commands: &Commands) {
+    fn update_time(&mut self) {
         self.previous_frame_ts = self.now_ts;
-        self.now_ts = commands.ts_now;
+        self.now_ts += TICK_DURATION;
     }
 }
 
-fn get_random_seed() -> u64 {
-    unsafe { now().floor().to_int_unchecked() }
+pub fn get_random_seed(clock: &impl Clock) -> u64 {
+    // saturates on negative and huge times, and gives 0 for NaN
+    clock.now() as u64
+}
+
+/// Height of the player's feet `jump_time` seconds after starting a jump.
+pub(crate) fn jump_height(jump_time: TimestampSeconds) -> f32 {
+    let height: f64 = 1.5;
+    let offset = JUMP_DURATION * 0.5;
+    // let jump_speed = (height/offset).sqrt();
+    let jump_speed = 1.0;
+    let height_coef = height / (offset * offset);
+    let x = jump_time * jump_speed - offset;
+    let y = height_coef * x * x;
+    (height - y) as f32
 }
 
-fn collides(player_pos: Vec3, obstacle_pos: Vec3) -> bool {
-    let obstacle_radius = 0.4;
-    let player_radius = 0.5;
-    let dpos = player_pos - obstacle_pos;
-    let squared_distance = dpos.dot(dpos);
-    let radius = obstacle_radius + player_radius;
-    let squared_min_distance = radius * radius;
-    squared_distance < squared_min_distance
+/// Whether two axis-aligned boxes overlap. Positions are at the center of the bottom face, like
+/// the player's feet, and sizes are full widths and heights.
+pub(crate) fn boxes_overlap(a_pos: Vec3, a_size: Vec3, b_pos: Vec3, b_size: Vec3) -> bool {
+    let gap = a_pos - b_pos;
+    let reach = (a_size + b_size) * 0.5;
+    gap.x.abs() < reach.x && gap.z.abs() < reach.z && gap.y < b_size.y && -gap.y < a_size.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::world::obstacles::{DEFAULT_GUARD_SPEED, DEFAULT_GUARD_TURN_RATE};
+    use crate::world::projectiles::{Aim, Weapon};
+
+    #[test]
+    fn test_movement_advances_per_tick() {
+        let mut world = World::new(0, &ManualClock::new(0.0));
+        world.obstacles.clear();
+        let commands = Commands {
+            forward_movement: Movement::Positive,
+            ..Commands::idle(0.0)
+        };
+        let ticks_per_second = (1.0 / TICK_DURATION).round() as i32;
+        for _ in 0..ticks_per_second {
+            world.update(&commands);
+        }
+        assert!((world.player_pos.x - SPEED).abs() < 0.001);
+        assert!((world.now_ts - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_jump_lands_after_jump_duration() {
+        let mut world = World::new(0, &ManualClock::new(0.0));
+        world.obstacles.clear();
+        let jump = Commands {
+            jump: true,
+            ..Commands::idle(0.0)
+        };
+        world.update(&jump);
+        world.update(&Commands::idle(0.0));
+        assert!(world.player_pos.y > 0.0);
+        let jump_ticks = (JUMP_DURATION / TICK_DURATION).ceil() as i32;
+        for _ in 0..jump_ticks {
+            world.update(&Commands::idle(0.0));
+        }
+        assert_eq!(world.player_pos.y, 0.0);
+    }
+
+    #[test]
+    fn test_random_seed_from_any_time() {
+        assert_eq!(get_random_seed(&ManualClock::new(1234.9)), 1234);
+        assert_eq!(get_random_seed(&ManualClock::new(-1.0)), 0);
+        assert_eq!(get_random_seed(&ManualClock::new(f64::NAN)), 0);
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let clock = ManualClock::new(1234.0);
+        let mut world_a = World::new(0, &clock);
+        let mut world_b = World::new(0, &clock);
+        assert_eq!(world_a.obstacles, world_b.obstacles);
+        world_a.next_level();
+        world_b.next_level();
+        assert_eq!(world_a.seed, world_b.seed);
+        assert_eq!(world_a.obstacles, world_b.obstacles);
+    }
+
+    #[test]
+    fn test_boss_level_ends_when_boss_is_defeated() {
+        let difficulty = Difficulty::default();
+        let boss_level = difficulty.boss_every_levels - 1;
+        let mut world = World::with_difficulty(boss_level, 1234, None, difficulty);
+        assert_eq!(world.piss, 1.0);
+        world.player_pos.x = world.finish_x + 10.0;
+        world.update(&Commands::idle(0.0));
+        assert!(!world.is_level_finished());
+        world.boss.as_mut().unwrap().hit(f32::MAX, world.now_ts);
+        assert!(world.is_level_finished());
+        world.next_level();
+        assert!(world.boss.is_none());
+    }
+
+    #[test]
+    fn test_endless_keeps_obstacles_around_player() {
+        let mut world = World::endless(1234, Difficulty::default());
+        assert!(!world.obstacles.is_empty());
+        world.player_pos.x = 500.0;
+        world.update(&Commands::idle(0.0));
+        let xs: Vec<f32> = world
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.get_pos(world.now_ts).x)
+            .collect();
+        assert!(xs.iter().all(|x| *x >= 500.0 - ENDLESS_DISCARD_DISTANCE));
+        assert!(*xs.last().unwrap() >= 500.0 + ENDLESS_VIEW_DISTANCE);
+        assert!(!world.is_level_finished());
+    }
+
+    #[test]
+    fn test_endless_keeps_guards_that_followed_the_player() {
+        let behaviour = Behaviour::Guard {
+            max_speed: DEFAULT_GUARD_SPEED,
+            turn_rate: DEFAULT_GUARD_TURN_RATE,
+        };
+        let mut guard = Obstacle::with_behaviour(Vec3::ZERO, behaviour.clone());
+        let player_pos = Vec3::new(20.0, 0.0, 0.0);
+        for _ in 0..1200 {
+            guard.update(TICK_DURATION, player_pos, &[]);
+        }
+        let mut world = World::endless(1234, Difficulty::default());
+        world.player_pos = player_pos;
+        world.obstacles.push_front(guard);
+        world.update(&Commands::idle(0.0));
+        assert!(world
+            .obstacles
+            .iter()
+            .any(|obstacle| *obstacle.behaviour() == behaviour));
+    }
+
+    #[test]
+    fn test_piss_goes_through_dead_obstacles() {
+        let mut world = World::new(0, &ManualClock::new(0.0));
+        world.obstacles.clear();
+        world.piss = 1.0;
+        let mut dead = Obstacle::new(2.4, 0.0, 0.0);
+        dead.kill(0.0);
+        world.obstacles.push_back(dead);
+        let mut alive = Obstacle::new(2.9, 0.0, 0.0);
+        alive.set_max_hp(100.0);
+        world.obstacles.push_back(alive);
+        let pissing = Commands {
+            pissing: true,
+            ..Commands::idle(0.0)
+        };
+        for _ in 0..60 {
+            world.update(&pissing);
+        }
+        assert!(world.obstacles[1].hp() < 100.0);
+    }
+
+    #[test]
+    fn test_turret_shots_hurt_unless_pissed_away() {
+        let turret_world = || {
+            let mut world = World::new(0, &ManualClock::new(0.0));
+            world.obstacles.clear();
+            world.piss = 1.0;
+            let weapon = Weapon {
+                aim: Aim::Forward,
+                period: 1.0,
+                phase: 0.5,
+                speed: 5.0,
+            };
+            world
+                .obstacles
+                .push_back(Obstacle::new(4.0, 0.0, 0.0).with_weapon(weapon));
+            world
+        };
+        let ticks = (2.0 / TICK_DURATION) as i32;
+        let mut shot = turret_world();
+        for _ in 0..ticks {
+            shot.update(&Commands::idle(0.0));
+        }
+        assert!(shot.health < 1.0);
+
+        let mut pissing = turret_world();
+        let commands = Commands {
+            pissing: true,
+            ..Commands::idle(0.0)
+        };
+        for _ in 0..ticks {
+            pissing.update(&commands);
+        }
+        assert_eq!(pissing.health, 1.0);
+    }
+
+    #[test]
+    fn test_obstacle_collision_matches_drawn_boxes() {
+        let obstacle = Obstacle::new(0.0, 0.0, 0.0);
+        let touches = |x, y, z| obstacle.collides(Vec3::new(x, y, z), PLAYER_SIZE, 0.0);
+        // corners count, unlike with spheres
+        assert!(touches(0.85, 0.0, 0.85));
+        assert!(!touches(0.95, 0.0, 0.0));
+        assert!(!touches(0.0, 0.0, 0.95));
+        // jumping just above the obstacle clears it
+        assert!(touches(0.0, 0.45, 0.0));
+        assert!(!touches(0.0, 0.55, 0.0));
+    }
+
+    #[test]
+    fn test_authored_level_only_replaces_its_own_level() {
+        let authored = LevelDescription {
+            obstacles: Vec::new(),
+            finish_x: Some(3.0),
+            health: None,
+            piss: None,
+            obstacle_hp: None,
+        };
+        let mut world = World::with_authored_level(0, 1234, Some(authored));
+        let start = |level, mode| Transition::Start {
+            level,
+            seed: 99,
+            difficulty: Difficulty::default(),
+            mode,
+        };
+        world.apply(start(3, Mode::Levels));
+        assert_eq!(world.obstacles, World::with_seed(3, 99).obstacles);
+        world.apply(start(0, Mode::Endless));
+        world.apply(Transition::RestartLevel);
+        world.apply(start(0, Mode::Levels));
+        assert!(world.obstacles.is_empty());
+        assert_eq!(world.finish_x, 3.0);
+    }
+
+    #[test]
+    fn test_restart_level_rebuilds_the_same_level() {
+        let mut world = World::with_seed(0, 1234);
+        world.next_level();
+        let fresh = World::with_seed(0, 1234);
+        let (health, piss) = (world.health, world.piss);
+        world.player_pos.x = 7.0;
+        world.obstacles[0].kill(world.now_ts);
+        world.health = 0.2;
+        world.piss = 0.0;
+        world.piss_used = 0.5;
+        world.apply(Transition::RestartLevel);
+        assert_eq!(world.level, 1);
+        assert_eq!(world.player_pos, Vec3::ZERO);
+        assert!(world.obstacles.iter().all(Obstacle::is_alive));
+        assert_eq!((world.health, world.piss), (health, piss));
+        assert_eq!(world.piss_used, 0.0);
+
+        let authored = LevelDescription {
+            obstacles: Vec::new(),
+            finish_x: Some(3.0),
+            health: None,
+            piss: None,
+            obstacle_hp: None,
+        };
+        let mut world = World::with_authored_level(0, 1234, Some(authored));
+        world.obstacles = fresh.obstacles;
+        world.apply(Transition::RestartLevel);
+        assert!(world.obstacles.is_empty());
+        assert_eq!(world.finish_x, 3.0);
+    }
+
+    #[test]
+    fn test_finishing_a_level_scores_and_restarting_it_forgets() {
+        let mut world = World::with_seed(0, 1234);
+        world.obstacles.clear();
+        world.finish_x = 2.0;
+        let forward = Commands {
+            forward_movement: Movement::Positive,
+            ..Commands::idle(0.0)
+        };
+        while world.update(&forward) != Some(Event::LevelFinished) {}
+        // the world keeps running behind the level-complete dialog
+        world.update(&forward);
+        assert_eq!(world.score.meters(), 2.0);
+        assert!(world.score.time_bonus > 0);
+        assert_eq!(world.score.health_bonus, 500);
+        let finished = world.score;
+
+        world.apply(Transition::NextLevel);
+        world.obstacles.clear();
+        for _ in 0..60 {
+            world.update(&forward);
+        }
+        world.score.kill();
+        assert!(world.score.total() > finished.total());
+        world.apply(Transition::RestartLevel);
+        assert_eq!(world.score, finished);
+    }
 }
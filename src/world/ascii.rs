@@ -0,0 +1,153 @@
+//! Plain-text level format, one row per depth unit and one column per lane:
+//!
+//! ```text
+//! ....
+//! ....
+//! ....
+//! #..#
+//! >...
+//! ```
+//!
+//! `.` is an empty cell, `#` a static obstacle, and `>`/`<` an obstacle moving right/left across
+//! all the lanes, so its column doesn't matter. `G` is a security guard starting in that lane, `T`
+//! a turret firing down the tunnel and `C` a cannon firing across the lanes. The first row is at
+//! depth 0. Other behaviours have no cell of their own.
+
+use crate::common::{AnyError, TimestampSeconds};
+use crate::world::behaviour::Behaviour;
+use crate::world::level::{LevelDescription, ObstacleDescription};
+use crate::world::obstacles::{
+    lane_z, Obstacles, DEFAULT_GUARD_SPEED, DEFAULT_GUARD_TURN_RATE, DEFAULT_OBSTACLE_SPEED,
+    DEFAULT_PATH_LENGTH, LANES, MOVING_START_Z,
+};
+use crate::world::projectiles::{Aim, DEFAULT_FIRING_PERIOD, DEFAULT_PROJECTILE_SPEED};
+
+const EMPTY: char = '.';
+const STATIC: char = '#';
+const MOVING_RIGHT: char = '>';
+const MOVING_LEFT: char = '<';
+const GUARD: char = 'G';
+const TURRET: char = 'T';
+const CANNON: char = 'C';
+
+pub fn parse(text: &str) -> Result<LevelDescription, AnyError> {
+    let mut obstacles = Vec::new();
+    for (depth, line) in text.lines().enumerate() {
+        let row = line.trim_end();
+        if row.chars().count() != LANES as usize {
+            return Err(format!(
+                "line {} should have {} cells but has {}: {:?}",
+                depth + 1,
+                LANES,
+                row.chars().count(),
+                row
+            )
+            .into());
+        }
+        let x = depth as f32;
+        for (i_lane, cell) in row.chars().enumerate() {
+            let obstacle = match cell {
+                EMPTY => continue,
+                STATIC => ObstacleDescription::Static {
+                    x,
+                    z: lane_z(i_lane as i32),
+                },
+                MOVING_RIGHT | MOVING_LEFT => ObstacleDescription::Moving {
+                    x,
+                    z: MOVING_START_Z,
+                    moving_right: cell == MOVING_RIGHT,
+                    speed: DEFAULT_OBSTACLE_SPEED,
+                    path_length: DEFAULT_PATH_LENGTH,
+                },
+                GUARD => ObstacleDescription::Guard {
+                    x,
+                    z: lane_z(i_lane as i32),
+                    max_speed: DEFAULT_GUARD_SPEED,
+                    turn_rate: DEFAULT_GUARD_TURN_RATE,
+                },
+                TURRET | CANNON => ObstacleDescription::Turret {
+                    x,
+                    z: lane_z(i_lane as i32),
+                    aim: if cell == TURRET {
+                        Aim::Forward
+                    } else {
+                        Aim::Across
+                    },
+                    period: DEFAULT_FIRING_PERIOD,
+                    // neighbouring rows take turns
+                    phase: x as TimestampSeconds * 0.5,
+                    speed: DEFAULT_PROJECTILE_SPEED,
+                },
+                _ => return Err(format!("unknown cell {:?} at line {}", cell, depth + 1).into()),
+            };
+            obstacles.push(obstacle);
+        }
+    }
+    Ok(LevelDescription {
+        obstacles,
+        finish_x: None,
+        health: None,
+        piss: None,
+        obstacle_hp: None,
+    })
+}
+
+/// Positions are rounded to the nearest cell, and moving obstacles lose their speed and path.
+/// Obstacles with other behaviours are exported as static ones, where they start.
+pub fn export(obstacles: &Obstacles) -> String {
+    let depth = obstacles
+        .iter()
+        .map(|obstacle| obstacle.origin().x.round() as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut rows = vec![vec![EMPTY; LANES as usize]; depth];
+    for obstacle in obstacles {
+        let pos = obstacle.origin();
+        let (i_lane, cell) = match obstacle.behaviour() {
+            Behaviour::Sweep {
+                moving_right: true, ..
+            } => (0, MOVING_RIGHT),
+            Behaviour::Sweep {
+                moving_right: false,
+                ..
+            } => (0, MOVING_LEFT),
+            behaviour => {
+                let i_lane = (pos.z - lane_z(0)).round().clamp(0.0, (LANES - 1) as f32);
+                let cell = match (behaviour, obstacle.weapon()) {
+                    (Behaviour::Guard { .. }, _) => GUARD,
+                    (_, Some(weapon)) if weapon.aim == Aim::Forward => TURRET,
+                    (_, Some(_)) => CANNON,
+                    _ => STATIC,
+                };
+                (i_lane as usize, cell)
+            }
+        };
+        rows[pos.x.round().max(0.0) as usize][i_lane] = cell;
+    }
+    rows.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::difficulty::Difficulty;
+    use crate::world::obstacles::generate_obstacles;
+
+    #[test]
+    fn test_round_trip_generated_levels() {
+        for level in [0, 5, 20, 60] {
+            let obstacles = generate_obstacles(level, 1234, &Difficulty::default());
+            let text = export(&obstacles);
+            let parsed = parse(&text).unwrap().to_level().obstacles;
+            assert_eq!(parsed, obstacles, "level {}:\n{}", level, text);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_rows() {
+        assert!(parse("....\n..#\n").is_err());
+        assert!(parse("..x.\n").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! How obstacles move. Every behaviour but `Chase` and `Guard` is a function of time alone, so
+//! the position of an obstacle can be predicted at any timestamp.
+
+use crate::common::TimestampSeconds;
+use glam::Vec3;
+use std::f64::consts::PI;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Behaviour {
+    Static,
+    /// Sweeps `path_length` units along z, then wraps around to the start.
+    Sweep {
+        moving_right: bool,
+        speed: f64,
+        path_length: f64,
+        /// Distance already travelled at timestamp 0.
+        phase: f64,
+    },
+    /// Walks `distance` units forward along x and back.
+    Patrol {
+        distance: f64,
+        speed: f64,
+        /// Distance already travelled at timestamp 0.
+        phase: f64,
+    },
+    /// Weaves from side to side along z, `amplitude` units each way, once every `period`.
+    Weave {
+        amplitude: f64,
+        period: TimestampSeconds,
+        phase: TimestampSeconds,
+    },
+    /// Hops up to `height`, once every `period`.
+    Hop {
+        height: f64,
+        period: TimestampSeconds,
+        phase: TimestampSeconds,
+    },
+    /// Loops smoothly through `points`, relative to the starting position, once every `period`.
+    Spline {
+        points: Vec<Vec3>,
+        period: TimestampSeconds,
+        phase: TimestampSeconds,
+    },
+    /// Walks straight towards the player at `speed`.
+    Chase {
+        speed: f32,
+    },
+    /// Security guard: steers towards the player, turning at most `turn_rate` radians per second,
+    /// staying in the tunnel and walking around static obstacles.
+    Guard {
+        max_speed: f32,
+        turn_rate: f32,
+    },
+}
+
+impl Behaviour {
+    /// Offset from the starting position at `ts`, for behaviours that only depend on time.
+    pub fn offset(&self, ts: TimestampSeconds) -> Vec3 {
+        match self {
+            Behaviour::Static | Behaviour::Chase { .. } | Behaviour::Guard { .. } => Vec3::ZERO,
+            Behaviour::Sweep {
+                moving_right,
+                speed,
+                path_length,
+                phase,
+            } => {
+                let offset = ((ts * speed + phase) % path_length) as f32;
+                let z = if *moving_right {
+                    offset
+                } else {
+                    *path_length as f32 - offset
+                };
+                Vec3::new(0.0, 0.0, z)
+            }
+            Behaviour::Patrol {
+                distance,
+                speed,
+                phase,
+            } => {
+                let travelled = (ts * speed + phase) % (2.0 * distance);
+                let x = if travelled < *distance {
+                    travelled
+                } else {
+                    2.0 * distance - travelled
+                };
+                Vec3::new(x as f32, 0.0, 0.0)
+            }
+            Behaviour::Weave {
+                amplitude,
+                period,
+                phase,
+            } => {
+                let z = amplitude * (2.0 * PI * (ts + phase) / period).sin();
+                Vec3::new(0.0, 0.0, z as f32)
+            }
+            Behaviour::Hop {
+                height,
+                period,
+                phase,
+            } => {
+                let y = height * (PI * (ts + phase) / period).sin().abs();
+                Vec3::new(0.0, y as f32, 0.0)
+            }
+            Behaviour::Spline {
+                points,
+                period,
+                phase,
+            } => spline_point(points, ((ts + phase) / period).rem_euclid(1.0) as f32),
+        }
+    }
+
+    /// Seconds after which the behaviour repeats itself, if it ever moves and repeats.
+    pub fn period(&self) -> Option<TimestampSeconds> {
+        match self {
+            Behaviour::Static | Behaviour::Chase { .. } | Behaviour::Guard { .. } => None,
+            Behaviour::Sweep {
+                speed, path_length, ..
+            } => Some(path_length / speed),
+            Behaviour::Patrol {
+                distance, speed, ..
+            } => Some(2.0 * distance / speed),
+            Behaviour::Weave { period, .. } | Behaviour::Spline { period, .. } => Some(*period),
+            Behaviour::Hop { period, .. } => Some(*period),
+        }
+    }
+
+    /// Range of offsets along x the behaviour can reach, or None if it can go anywhere.
+    pub fn x_range(&self) -> Option<(f32, f32)> {
+        match self {
+            Behaviour::Chase { .. } | Behaviour::Guard { .. } => None,
+            Behaviour::Patrol { distance, .. } => Some((0.0, *distance as f32)),
+            Behaviour::Spline { points, .. } => {
+                Some(points.iter().fold((0.0, 0.0), |(min, max), point| {
+                    (point.x.min(min), point.x.max(max))
+                }))
+            }
+            _ => Some((0.0, 0.0)),
+        }
+    }
+}
+
+/// Point of the closed Catmull-Rom curve through `points`, `fraction` of the way around it.
+fn spline_point(points: &[Vec3], fraction: f32) -> Vec3 {
+    let n = points.len();
+    if n == 0 {
+        return Vec3::ZERO;
+    }
+    let t = fraction * n as f32;
+    let i = (t.floor() as usize).min(n - 1);
+    let u = t - i as f32;
+    let p0 = points[(i + n - 1) % n];
+    let p1 = points[i];
+    let p2 = points[(i + 1) % n];
+    let p3 = points[(i + 2) % n];
+    0.5 * (2.0 * p1
+        + (p2 - p0) * u
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u * u
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u * u * u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spline_goes_through_its_points() {
+        let points = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 2.0),
+        ];
+        let spline = Behaviour::Spline {
+            points: points.clone(),
+            period: 3.0,
+            phase: 0.0,
+        };
+        for (i, point) in points.iter().enumerate() {
+            assert!((spline.offset(i as f64) - *point).length() < 0.001);
+        }
+        assert!((spline.offset(3.0) - points[0]).length() < 0.001);
+    }
+
+    #[test]
+    fn test_patrol_turns_around() {
+        let patrol = Behaviour::Patrol {
+            distance: 4.0,
+            speed: 2.0,
+            phase: 0.0,
+        };
+        assert_eq!(patrol.offset(1.0).x, 2.0);
+        assert_eq!(patrol.offset(3.0).x, 2.0);
+        assert_eq!(patrol.offset(4.0).x, 0.0);
+    }
+}
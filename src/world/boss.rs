@@ -0,0 +1,198 @@
+//! The corporation: a boss waiting at the end of every few levels, firing down the tunnel until
+//! it is pissed to death.
+
+use crate::common::TimestampSeconds;
+use crate::world::obstacles::{lane_z, LANES};
+use crate::world::projectiles::Projectile;
+use crate::world::{boxes_overlap, FULL_BLADDER_DAMAGE};
+use glam::Vec3;
+use std::f64::consts::PI;
+
+/// How far from the start of its level the boss stands.
+pub const BOSS_DISTANCE: f32 = 25.0;
+/// Half of the boss's width along x and z. It is as tall as `BOSS_HEIGHT`.
+pub const BOSS_HALF_WIDTH: f32 = 1.0;
+pub const BOSS_HEIGHT: f32 = 3.0;
+pub const BOSS_SIZE: Vec3 = Vec3::new(2.0 * BOSS_HALF_WIDTH, BOSS_HEIGHT, 2.0 * BOSS_HALF_WIDTH);
+const BASE_HP: f32 = 150.0;
+const HP_PER_BOSS: f32 = 50.0;
+/// Boss levels start with a full piss bar and nothing refills it, so later bosses stop getting
+/// tougher well before they would outlast it, leaving room for missed particles.
+const MAX_HP: f32 = 0.75 * FULL_BLADDER_DAMAGE;
+/// How far the boss sways to each side.
+const SWAY_AMPLITUDE: f32 = 0.5;
+
+/// How the boss fights while its health is within one third of the full bar.
+struct Phase {
+    attack_period: TimestampSeconds,
+    projectile_speed: f32,
+    sway_period: TimestampSeconds,
+}
+
+const PHASES: [Phase; 3] = [
+    // single shots at wherever it is facing
+    Phase {
+        attack_period: 1.0,
+        projectile_speed: 6.0,
+        sway_period: 4.0,
+    },
+    // volleys leaving a single free lane, which moves along with each volley
+    Phase {
+        attack_period: 1.4,
+        projectile_speed: 6.0,
+        sway_period: 3.0,
+    },
+    // faster volleys, the free lane jumping around
+    Phase {
+        attack_period: 0.8,
+        projectile_speed: 8.0,
+        sway_period: 2.0,
+    },
+];
+
+pub struct Boss {
+    pub pos: Vec3,
+    hp: f32,
+    max_hp: f32,
+    last_hit: Option<TimestampSeconds>,
+    last_attack: TimestampSeconds,
+    attacks: u32,
+}
+
+impl Boss {
+    /// The `boss_number`th boss of the run, starting at 1, is tougher than the previous ones.
+    pub fn new(boss_number: i32) -> Self {
+        let hp = (BASE_HP + HP_PER_BOSS * (boss_number - 1).max(0) as f32).min(MAX_HP);
+        Self {
+            pos: Vec3::new(BOSS_DISTANCE, 0.0, 0.0),
+            hp,
+            max_hp: hp,
+            last_hit: None,
+            last_attack: 0.0,
+            attacks: 0,
+        }
+    }
+
+    /// 0, 1 or 2, getting more aggressive as its health goes down.
+    pub fn phase(&self) -> usize {
+        let lost = 1.0 - self.hp / self.max_hp;
+        ((lost * PHASES.len() as f32) as usize).min(PHASES.len() - 1)
+    }
+
+    /// Moves and attacks according to the world timer.
+    pub fn update(&mut self, now_ts: TimestampSeconds, projectiles: &mut Vec<Projectile>) {
+        if self.is_defeated() {
+            return;
+        }
+        let phase = &PHASES[self.phase()];
+        let sway = (2.0 * PI * now_ts / phase.sway_period).sin() as f32;
+        self.pos.z = SWAY_AMPLITUDE * sway;
+        if now_ts - self.last_attack < phase.attack_period {
+            return;
+        }
+        self.last_attack = now_ts;
+        self.attacks += 1;
+        let muzzle_x = self.pos.x - BOSS_HALF_WIDTH;
+        let velocity = Vec3::new(-phase.projectile_speed, 0.0, 0.0);
+        let mut fire =
+            |z: f32| projectiles.push(Projectile::new(Vec3::new(muzzle_x, 0.0, z), velocity));
+        match self.phase() {
+            0 => fire(self.pos.z),
+            phase => {
+                let free_lane = if phase == 1 {
+                    self.attacks % LANES as u32
+                } else {
+                    self.attacks.wrapping_mul(7) % LANES as u32
+                };
+                for i_lane in 0..LANES {
+                    if i_lane as u32 != free_lane {
+                        fire(lane_z(i_lane));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Takes damage. Returns whether it defeated the boss.
+    pub fn hit(&mut self, damage: f32, ts: TimestampSeconds) -> bool {
+        if self.is_defeated() {
+            return false;
+        }
+        self.hp = (self.hp - damage).max(0.0);
+        self.last_hit = Some(ts);
+        self.is_defeated()
+    }
+
+    /// Whether a point, e.g. a piss particle, is inside the boss.
+    pub fn contains(&self, pos: Vec3) -> bool {
+        self.touches(pos, Vec3::ZERO)
+    }
+
+    /// Whether a box of the given size standing at `pos` touches the boss.
+    pub fn touches(&self, pos: Vec3, size: Vec3) -> bool {
+        !self.is_defeated() && boxes_overlap(pos, size, self.pos, BOSS_SIZE)
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.hp == 0.0
+    }
+
+    pub fn hp(&self) -> f32 {
+        self.hp
+    }
+
+    pub fn max_hp(&self) -> f32 {
+        self.max_hp
+    }
+
+    pub fn last_hit(&self) -> Option<TimestampSeconds> {
+        self.last_hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::difficulty::{Difficulty, PRESET_NAMES};
+
+    #[test]
+    fn test_volleys_leave_a_free_lane() {
+        let mut boss = Boss::new(1);
+        boss.hit(boss.max_hp() * 0.5, 0.0);
+        assert_eq!(boss.phase(), 1);
+        let mut projectiles = Vec::new();
+        boss.update(10.0, &mut projectiles);
+        assert_eq!(projectiles.len(), LANES as usize - 1);
+        projectiles.clear();
+        boss.update(10.1, &mut projectiles);
+        assert!(projectiles.is_empty());
+    }
+
+    #[test]
+    fn test_defeated_boss_stops_attacking() {
+        let mut boss = Boss::new(2);
+        assert!(!boss.hit(BASE_HP, 0.0));
+        assert_eq!(boss.phase(), 2);
+        assert!(boss.hit(HP_PER_BOSS, 0.1));
+        let mut projectiles = Vec::new();
+        boss.update(10.0, &mut projectiles);
+        assert!(projectiles.is_empty());
+        assert!(!boss.contains(boss.pos));
+    }
+
+    #[test]
+    fn test_every_boss_dies_within_one_bladder() {
+        for name in PRESET_NAMES {
+            let difficulty = Difficulty::preset(name).unwrap();
+            let last_boss = (0..1000)
+                .filter_map(|level| difficulty.boss_number(level))
+                .next_back()
+                .unwrap();
+            assert!(
+                Boss::new(last_boss).max_hp() < FULL_BLADDER_DAMAGE,
+                "{}",
+                name
+            );
+        }
+    }
+}
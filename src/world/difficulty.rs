@@ -0,0 +1,251 @@
+use crate::common::AnyError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+pub const PRESET_NAMES: [&str; 3] = ["easy", "normal", "hard"];
+
+/// How the generated levels get harder as the level number grows.
+///
+/// Chances are percentages. Profile files are JSON, and any knob they leave out keeps its value
+/// from the normal preset. Unknown knobs are rejected, so that a typo doesn't go unnoticed.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Difficulty {
+    pub base_obstacles: i32,
+    pub obstacles_per_level: i32,
+    pub moving_chance_per_level: i32,
+    pub max_moving_chance: i32,
+    pub base_static_chance: i32,
+    pub static_chance_per_level: i32,
+    pub max_static_chance: i32,
+    /// Distance along x between consecutive rows of obstacles.
+    pub row_spacing: f32,
+    /// Chance of a level being stitched from authored patterns instead of rolling each lane.
+    pub pattern_chance: i32,
+    /// Extra piss particles it takes to kill an obstacle, for each level.
+    pub obstacle_hp_per_level: f32,
+    pub max_obstacle_hp: f32,
+    /// Every this many levels, the level is a boss fight instead. 0 for no bosses.
+    pub boss_every_levels: i32,
+}
+
+impl Difficulty {
+    pub fn easy() -> Self {
+        Self {
+            base_obstacles: 10,
+            obstacles_per_level: 1,
+            moving_chance_per_level: 1,
+            max_moving_chance: 60,
+            base_static_chance: 10,
+            static_chance_per_level: 1,
+            max_static_chance: 60,
+            row_spacing: 2.0,
+            pattern_chance: 30,
+            obstacle_hp_per_level: 0.25,
+            max_obstacle_hp: 15.0,
+            boss_every_levels: 6,
+        }
+    }
+
+    pub fn normal() -> Self {
+        Self {
+            base_obstacles: 15,
+            obstacles_per_level: 1,
+            moving_chance_per_level: 2,
+            max_moving_chance: 90,
+            base_static_chance: 15,
+            static_chance_per_level: 1,
+            max_static_chance: 80,
+            row_spacing: 1.0,
+            pattern_chance: 30,
+            obstacle_hp_per_level: 0.5,
+            max_obstacle_hp: 30.0,
+            boss_every_levels: 5,
+        }
+    }
+
+    pub fn hard() -> Self {
+        Self {
+            base_obstacles: 20,
+            obstacles_per_level: 2,
+            moving_chance_per_level: 3,
+            max_moving_chance: 90,
+            base_static_chance: 25,
+            static_chance_per_level: 2,
+            max_static_chance: 85,
+            row_spacing: 1.0,
+            pattern_chance: 50,
+            obstacle_hp_per_level: 1.0,
+            max_obstacle_hp: 60.0,
+            boss_every_levels: 4,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Self::easy()),
+            "normal" => Some(Self::normal()),
+            "hard" => Some(Self::hard()),
+            _ => None,
+        }
+    }
+
+    /// Name of the preset with exactly these knobs, if any.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        PRESET_NAMES
+            .into_iter()
+            .find(|name| Self::preset(name).as_ref() == Some(self))
+    }
+
+    /// Takes a preset name, or the path of a JSON profile file.
+    pub fn load(name_or_path: &str) -> Result<Self, AnyError> {
+        if let Some(preset) = Self::preset(name_or_path) {
+            return Ok(preset);
+        }
+        let text = fs::read_to_string(name_or_path).map_err(|e| {
+            format!(
+                "difficulty {:?} is neither one of {:?} nor a readable file: {}",
+                name_or_path, PRESET_NAMES, e
+            )
+        })?;
+        let difficulty: Self = serde_json::from_str(&text)
+            .map_err(|e| format!("invalid difficulty file {}: {}", name_or_path, e))?;
+        difficulty
+            .validate()
+            .map_err(|e| format!("invalid difficulty file {}: {}", name_or_path, e))?;
+        Ok(difficulty)
+    }
+
+    /// Rejects knobs out of their range, e.g. a negative count or a chance over 100%.
+    fn validate(&self) -> Result<(), String> {
+        let counts = [
+            ("base_obstacles", self.base_obstacles),
+            ("obstacles_per_level", self.obstacles_per_level),
+            ("boss_every_levels", self.boss_every_levels),
+        ];
+        for (name, value) in counts {
+            if value < 0 {
+                return Err(format!("{} should not be negative, got {}", name, value));
+            }
+        }
+        let chances = [
+            ("moving_chance_per_level", self.moving_chance_per_level),
+            ("max_moving_chance", self.max_moving_chance),
+            ("base_static_chance", self.base_static_chance),
+            ("static_chance_per_level", self.static_chance_per_level),
+            ("max_static_chance", self.max_static_chance),
+            ("pattern_chance", self.pattern_chance),
+        ];
+        for (name, value) in chances {
+            if !(0..=100).contains(&value) {
+                return Err(format!(
+                    "{} should be between 0 and 100, got {}",
+                    name, value
+                ));
+            }
+        }
+        if !self.row_spacing.is_finite() || self.row_spacing <= 0.0 {
+            return Err(format!(
+                "row_spacing should be positive, got {}",
+                self.row_spacing
+            ));
+        }
+        if !self.obstacle_hp_per_level.is_finite() || self.obstacle_hp_per_level < 0.0 {
+            return Err(format!(
+                "obstacle_hp_per_level should not be negative, got {}",
+                self.obstacle_hp_per_level
+            ));
+        }
+        if !self.max_obstacle_hp.is_finite() || self.max_obstacle_hp < 1.0 {
+            return Err(format!(
+                "max_obstacle_hp should be at least 1, got {}",
+                self.max_obstacle_hp
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn obstacles(&self, level: i32) -> i32 {
+        self.base_obstacles + self.obstacles_per_level * level
+    }
+
+    pub fn moving_chance(&self, level: i32) -> i32 {
+        (self.moving_chance_per_level * level).min(self.max_moving_chance)
+    }
+
+    /// Piss particles it takes to kill an obstacle. It takes a single one on the first level, and
+    /// never less.
+    pub fn obstacle_hp(&self, level: i32) -> f32 {
+        (1.0 + self.obstacle_hp_per_level * level as f32)
+            .min(self.max_obstacle_hp)
+            .max(1.0)
+    }
+
+    /// How many bosses were met up to the given level, if it is a boss level.
+    pub fn boss_number(&self, level: i32) -> Option<i32> {
+        if self.boss_every_levels > 0 && (level + 1) % self.boss_every_levels == 0 {
+            Some((level + 1) / self.boss_every_levels)
+        } else {
+            None
+        }
+    }
+
+    pub fn static_chance(&self, level: i32) -> i32 {
+        (self.base_static_chance + self.static_chance_per_level * level).min(self.max_static_chance)
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::normal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_profile_keeps_normal_knobs() {
+        let difficulty: Difficulty =
+            serde_json::from_str(r#"{"base_obstacles": 40, "row_spacing": 2}"#).unwrap();
+        assert_eq!(difficulty.obstacles(3), 43);
+        assert_eq!(difficulty.row_spacing, 2.0);
+        assert_eq!(difficulty.moving_chance(100), 90);
+        assert_eq!(difficulty.preset_name(), None);
+        assert_eq!(
+            Difficulty::load("hard").unwrap().preset_name(),
+            Some("hard")
+        );
+    }
+
+    #[test]
+    fn test_obstacles_always_take_a_hit_to_kill() {
+        let difficulty: Difficulty =
+            serde_json::from_str(r#"{"max_obstacle_hp": 0, "obstacle_hp_per_level": -1}"#).unwrap();
+        assert_eq!(difficulty.obstacle_hp(0), 1.0);
+        assert_eq!(difficulty.obstacle_hp(5), 1.0);
+    }
+
+    #[test]
+    fn test_out_of_range_knobs_are_rejected() {
+        for profile in [
+            r#"{"row_spacing": 0}"#,
+            r#"{"base_obstacles": -1}"#,
+            r#"{"max_static_chance": 101}"#,
+            r#"{"max_obstacle_hp": 0}"#,
+        ] {
+            let difficulty: Difficulty = serde_json::from_str(profile).unwrap();
+            assert!(difficulty.validate().is_err(), "{}", profile);
+        }
+        for name in PRESET_NAMES {
+            assert!(Difficulty::preset(name).unwrap().validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_unknown_knob_is_rejected() {
+        let misspelled = serde_json::from_str::<Difficulty>(r#"{"base_obstacle": 40}"#);
+        assert!(misspelled.is_err());
+    }
+}
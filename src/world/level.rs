@@ -0,0 +1,453 @@
+use crate::common::{AnyError, TimestampSeconds};
+use crate::world::behaviour::Behaviour;
+use crate::world::boss::{Boss, BOSS_DISTANCE, BOSS_HALF_WIDTH};
+use crate::world::difficulty::Difficulty;
+use crate::world::obstacles::{
+    generate_obstacles, Obstacle, Obstacles, DEFAULT_GUARD_SPEED, DEFAULT_GUARD_TURN_RATE,
+    DEFAULT_OBSTACLE_SPEED, DEFAULT_PATH_LENGTH,
+};
+use crate::world::patterns::{builtin_patterns, generate_from_patterns};
+use crate::world::projectiles::{Aim, Weapon, DEFAULT_FIRING_PERIOD, DEFAULT_PROJECTILE_SPEED};
+use crate::world::rng::Rng;
+use crate::world::{ascii, solver};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const FINISH_DISTANCE: f32 = 10.0;
+
+/// A level ready to be played.
+pub struct Level {
+    pub obstacles: Obstacles,
+    /// Boss to defeat to complete the level, instead of reaching the finish.
+    pub boss: Option<Boss>,
+    /// The level is completed when the player goes past this x.
+    pub finish_x: f32,
+    /// Health and piss to start the level with, instead of keeping the previous level's.
+    pub health: Option<f32>,
+    pub piss: Option<f32>,
+}
+
+/// Ways of laying out the obstacles of a generated level.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Generator {
+    /// Rolls each lane of each row independently.
+    Lanes,
+    /// Stitches together hand-authored patterns.
+    Patterns,
+}
+
+impl Generator {
+    /// Picks the generator of the level with this seed.
+    pub fn for_level(seed: u64, difficulty: &Difficulty) -> Self {
+        // not the same random sequence as the one laying out the obstacles
+        let mut rng = Rng::new(!seed);
+        if rng.gen_range(0, 100) < difficulty.pattern_chance {
+            Generator::Patterns
+        } else {
+            Generator::Lanes
+        }
+    }
+}
+
+impl Level {
+    /// Generates a level that can always be completed without touching any obstacle.
+    pub fn generate(level: i32, seed: u64, difficulty: &Difficulty) -> Self {
+        let generator = Generator::for_level(seed, difficulty);
+        Self::generate_with(generator, level, seed, difficulty)
+    }
+
+    pub fn generate_with(
+        generator: Generator,
+        level: i32,
+        seed: u64,
+        difficulty: &Difficulty,
+    ) -> Self {
+        let mut obstacles = match generator {
+            Generator::Lanes => generate_obstacles(level, seed, difficulty),
+            Generator::Patterns => {
+                generate_from_patterns(level, seed, difficulty, builtin_patterns())
+                    .expect("the builtin patterns should be valid")
+            }
+        };
+        solver::make_solvable(&mut obstacles);
+        for obstacle in &mut obstacles {
+            obstacle.set_max_hp(difficulty.obstacle_hp(level));
+        }
+        Self {
+            finish_x: default_finish_x(&obstacles),
+            obstacles,
+            boss: None,
+            health: None,
+            piss: None,
+        }
+    }
+
+    /// An empty tunnel with the `boss_number`th boss at the end, and a full bladder to fight it.
+    pub fn boss(boss_number: i32) -> Self {
+        Self {
+            obstacles: Obstacles::new(),
+            boss: Some(Boss::new(boss_number)),
+            finish_x: BOSS_DISTANCE + BOSS_HALF_WIDTH,
+            health: None,
+            piss: Some(1.0),
+        }
+    }
+}
+
+/// Hand-authored level, as written in a level file.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LevelDescription {
+    pub obstacles: Vec<ObstacleDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_x: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub piss: Option<f32>,
+    /// Piss particles it takes to kill each obstacle, one if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obstacle_hp: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObstacleDescription {
+    Static {
+        x: f32,
+        z: f32,
+    },
+    Moving {
+        x: f32,
+        z: f32,
+        moving_right: bool,
+        #[serde(default = "default_speed")]
+        speed: f64,
+        #[serde(default = "default_path_length")]
+        path_length: f64,
+    },
+    Patrol {
+        x: f32,
+        z: f32,
+        distance: f64,
+        speed: f64,
+        #[serde(default)]
+        phase: f64,
+    },
+    Weave {
+        x: f32,
+        z: f32,
+        amplitude: f64,
+        period: TimestampSeconds,
+        #[serde(default)]
+        phase: TimestampSeconds,
+    },
+    Hop {
+        x: f32,
+        z: f32,
+        height: f64,
+        period: TimestampSeconds,
+        #[serde(default)]
+        phase: TimestampSeconds,
+    },
+    Spline {
+        x: f32,
+        z: f32,
+        /// `[x, y, z]` offsets from the starting position.
+        points: Vec<[f32; 3]>,
+        period: TimestampSeconds,
+        #[serde(default)]
+        phase: TimestampSeconds,
+    },
+    Chase {
+        x: f32,
+        z: f32,
+        speed: f32,
+    },
+    Guard {
+        x: f32,
+        z: f32,
+        #[serde(default = "default_guard_speed")]
+        max_speed: f32,
+        #[serde(default = "default_guard_turn_rate")]
+        turn_rate: f32,
+    },
+    /// Static obstacle firing projectiles.
+    Turret {
+        x: f32,
+        z: f32,
+        #[serde(default)]
+        aim: Aim,
+        #[serde(default = "default_firing_period")]
+        period: TimestampSeconds,
+        #[serde(default)]
+        phase: TimestampSeconds,
+        #[serde(default = "default_projectile_speed")]
+        speed: f32,
+    },
+}
+
+impl LevelDescription {
+    /// Reads a JSON level file, or a plain-text one if its name ends in `.txt`.
+    pub fn load(path: &str) -> Result<Self, AnyError> {
+        let text = fs::read_to_string(path)?;
+        let description = if path.ends_with(".txt") {
+            ascii::parse(&text)
+        } else {
+            serde_json::from_str(&text).map_err(AnyError::from)
+        };
+        description
+            .and_then(|description| {
+                description.validate()?;
+                Ok(description)
+            })
+            .map_err(|e| format!("invalid level file {}: {}", path, e).into())
+    }
+
+    fn validate(&self) -> Result<(), AnyError> {
+        for (i, obstacle) in self.obstacles.iter().enumerate() {
+            obstacle
+                .validate()
+                .map_err(|e| format!("obstacle {} {:?}: {}", i + 1, obstacle, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn to_level(&self) -> Level {
+        let mut obstacles: Obstacles = self
+            .obstacles
+            .iter()
+            .map(ObstacleDescription::to_obstacle)
+            .collect();
+        if let Some(hp) = self.obstacle_hp {
+            for obstacle in &mut obstacles {
+                obstacle.set_max_hp(hp.max(1.0));
+            }
+        }
+        Level {
+            finish_x: self
+                .finish_x
+                .unwrap_or_else(|| default_finish_x(&obstacles)),
+            obstacles,
+            boss: None,
+            health: self.health.map(|health| health.clamp(0.0, 1.0)),
+            piss: self.piss.map(|piss| piss.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+impl ObstacleDescription {
+    /// Rejects the parameters that would leave the obstacle nowhere, like a zero period.
+    fn validate(&self) -> Result<(), String> {
+        let positive = match self {
+            ObstacleDescription::Static { .. } => vec![],
+            ObstacleDescription::Moving {
+                speed, path_length, ..
+            } => vec![("speed", *speed), ("path_length", *path_length)],
+            ObstacleDescription::Patrol {
+                distance, speed, ..
+            } => vec![("distance", *distance), ("speed", *speed)],
+            ObstacleDescription::Weave { period, .. }
+            | ObstacleDescription::Hop { period, .. }
+            | ObstacleDescription::Spline { period, .. } => vec![("period", *period)],
+            ObstacleDescription::Chase { speed, .. } => vec![("speed", *speed as f64)],
+            ObstacleDescription::Guard { max_speed, .. } => {
+                vec![("max_speed", *max_speed as f64)]
+            }
+            ObstacleDescription::Turret { period, speed, .. } => {
+                vec![("period", *period), ("speed", *speed as f64)]
+            }
+        };
+        for (name, value) in positive {
+            if value.is_nan() || value <= 0.0 {
+                return Err(format!("{} should be positive, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_obstacle(&self) -> Obstacle {
+        let (x, z, behaviour) = match self {
+            ObstacleDescription::Static { x, z } => (x, z, Behaviour::Static),
+            ObstacleDescription::Moving {
+                x,
+                z,
+                moving_right,
+                speed,
+                path_length,
+            } => {
+                return Obstacle::new_moving_along(*x, 0.0, *z, *moving_right, *speed, *path_length)
+            }
+            ObstacleDescription::Patrol {
+                x,
+                z,
+                distance,
+                speed,
+                phase,
+            } => (
+                x,
+                z,
+                Behaviour::Patrol {
+                    distance: *distance,
+                    speed: *speed,
+                    phase: *phase,
+                },
+            ),
+            ObstacleDescription::Weave {
+                x,
+                z,
+                amplitude,
+                period,
+                phase,
+            } => (
+                x,
+                z,
+                Behaviour::Weave {
+                    amplitude: *amplitude,
+                    period: *period,
+                    phase: *phase,
+                },
+            ),
+            ObstacleDescription::Hop {
+                x,
+                z,
+                height,
+                period,
+                phase,
+            } => (
+                x,
+                z,
+                Behaviour::Hop {
+                    height: *height,
+                    period: *period,
+                    phase: *phase,
+                },
+            ),
+            ObstacleDescription::Spline {
+                x,
+                z,
+                points,
+                period,
+                phase,
+            } => (
+                x,
+                z,
+                Behaviour::Spline {
+                    points: points.iter().map(|point| Vec3::from(*point)).collect(),
+                    period: *period,
+                    phase: *phase,
+                },
+            ),
+            ObstacleDescription::Chase { x, z, speed } => {
+                (x, z, Behaviour::Chase { speed: *speed })
+            }
+            ObstacleDescription::Guard {
+                x,
+                z,
+                max_speed,
+                turn_rate,
+            } => (
+                x,
+                z,
+                Behaviour::Guard {
+                    max_speed: *max_speed,
+                    turn_rate: *turn_rate,
+                },
+            ),
+            ObstacleDescription::Turret {
+                x,
+                z,
+                aim,
+                period,
+                phase,
+                speed,
+            } => {
+                let weapon = Weapon {
+                    aim: *aim,
+                    period: *period,
+                    phase: *phase,
+                    speed: *speed,
+                };
+                return Obstacle::new(*x, 0.0, *z).with_weapon(weapon);
+            }
+        };
+        Obstacle::with_behaviour(Vec3::new(*x, 0.0, *z), behaviour)
+    }
+}
+
+fn default_finish_x(obstacles: &Obstacles) -> f32 {
+    let last_x = obstacles
+        .iter()
+        .map(|obstacle| {
+            let (_, max) = obstacle.behaviour().x_range().unwrap_or_default();
+            obstacle.origin().x + max
+        })
+        .fold(0.0, f32::max);
+    last_x + FINISH_DISTANCE
+}
+
+fn default_speed() -> f64 {
+    DEFAULT_OBSTACLE_SPEED
+}
+
+fn default_path_length() -> f64 {
+    DEFAULT_PATH_LENGTH
+}
+
+fn default_firing_period() -> TimestampSeconds {
+    DEFAULT_FIRING_PERIOD
+}
+
+fn default_projectile_speed() -> f32 {
+    DEFAULT_PROJECTILE_SPEED
+}
+
+fn default_guard_speed() -> f32 {
+    DEFAULT_GUARD_SPEED
+}
+
+fn default_guard_turn_rate() -> f32 {
+    DEFAULT_GUARD_TURN_RATE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level_file() {
+        let text = r#"{
+            "piss": 0.5,
+            "obstacles": [
+                {"kind": "static", "x": 3, "z": -1.5},
+                {"kind": "moving", "x": 6, "z": -3, "moving_right": true, "speed": 1.5},
+                {"kind": "patrol", "x": 2, "z": 0, "distance": 3, "speed": 1}
+            ]
+        }"#;
+        let description: LevelDescription = serde_json::from_str(text).unwrap();
+        let level = description.to_level();
+        assert_eq!(level.obstacles[0], Obstacle::new(3.0, 0.0, -1.5));
+        assert_eq!(
+            level.obstacles[1],
+            Obstacle::new_moving_along(6.0, 0.0, -3.0, true, 1.5, DEFAULT_PATH_LENGTH)
+        );
+        assert_eq!(level.obstacles[2].get_pos(1.0), Vec3::new(3.0, 0.0, 0.0));
+        assert_eq!(level.finish_x, 16.0);
+        assert_eq!(level.health, None);
+        assert_eq!(level.piss, Some(0.5));
+    }
+
+    #[test]
+    fn test_obstacles_without_a_position_are_rejected() {
+        let text = r#"{
+            "obstacles": [
+                {"kind": "static", "x": 3, "z": -1.5},
+                {"kind": "moving", "x": 6, "z": -3, "moving_right": true, "speed": 0}
+            ]
+        }"#;
+        let description: LevelDescription = serde_json::from_str(text).unwrap();
+        let error = description.validate().unwrap_err().to_string();
+        assert!(error.starts_with("obstacle 2 Moving"), "{}", error);
+        assert!(error.contains("speed"), "{}", error);
+    }
+}
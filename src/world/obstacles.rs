@@ -1,120 +1,318 @@
 use crate::common::TimestampSeconds;
-use macroquad::prelude::*;
+use crate::world::behaviour::Behaviour;
+use crate::world::difficulty::Difficulty;
+use crate::world::projectiles::Weapon;
+use crate::world::rng::Rng;
+use crate::world::{boxes_overlap, TUNNEL_HALF_WIDTH};
+use glam::Vec3;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
 
+pub const DEFAULT_PATH_LENGTH: f64 = 6.0;
+pub const DEFAULT_OBSTACLE_SPEED: f64 = 3.0;
+pub const LANES: i32 = 4;
+/// Where moving obstacles start their sweep across all the lanes.
+pub const MOVING_START_Z: f32 = -3.0;
+pub const DEFAULT_GUARD_SPEED: f32 = 2.5;
+pub const DEFAULT_GUARD_TURN_RATE: f32 = 2.0;
+/// Guards start steering away from static obstacles closer than this.
+const GUARD_AVOID_DISTANCE: f32 = 1.5;
+/// Guards can't get closer than this to the center of a static obstacle.
+const GUARD_MIN_DISTANCE: f32 = 0.8;
+/// How far along x each hit pushes an obstacle.
+const KNOCKBACK_DISTANCE: f32 = 0.03;
+/// Size of an obstacle at full health, from the floor up.
+pub const OBSTACLE_SIZE: Vec3 = Vec3::new(0.8, 0.5, 0.8);
+/// Damaged obstacles shrink, down to this fraction of their size.
+const MIN_SIZE_FRACTION: f32 = 0.6;
+
+#[derive(PartialEq, Debug)]
 pub struct Obstacle {
     alive: bool,
-    position: Position,
-}
-
-pub enum Position {
-    Static {
-        pos: Vec3,
-    },
-    Moving {
-        initial_pos: Vec3,
-        salt: f64,
-        moving_right: bool,
-    },
+    /// Where the obstacle starts, and what its behaviour moves it relative to.
+    origin: Vec3,
+    behaviour: Behaviour,
+    /// Where the obstacle is, for behaviours that depend on more than time.
+    current_pos: Vec3,
+    /// Angle in radians of the walking direction on the floor, 0 being along x.
+    heading: f32,
+    hp: f32,
+    max_hp: f32,
+    last_hit: Option<TimestampSeconds>,
+    weapon: Option<Weapon>,
 }
 
-// might want to make a VecDeque to queue obstacles dynamically
-pub type Obstacles = Vec<Obstacle>;
+/// Sorted by x, so that endless runs can add obstacles ahead and drop the ones behind.
+pub type Obstacles = VecDeque<Obstacle>;
 
 impl Obstacle {
     pub fn new(x: f32, y: f32, z: f32) -> Self {
-        Self {
-            alive: true,
-            position: Position::Static {
-                pos: Vec3::new(x, y, z),
-            },
-        }
+        Self::with_behaviour(Vec3::new(x, y, z), Behaviour::Static)
     }
     pub fn new_moving(x: f32, y: f32, z: f32, moving_right: bool) -> Self {
-        Self {
-            alive: true,
-            position: Position::Moving {
-                initial_pos: Vec3::new(x, y, z),
-                salt: x as f64 % 100.0 * y as f64 % 100.0 + x as f64 + y as f64 + z as f64,
+        Self::new_moving_along(
+            x,
+            y,
+            z,
+            moving_right,
+            DEFAULT_OBSTACLE_SPEED,
+            DEFAULT_PATH_LENGTH,
+        )
+    }
+    /// Sweeps `path_length` units along z starting at `z`, then wraps around to the start.
+    pub fn new_moving_along(
+        x: f32,
+        y: f32,
+        z: f32,
+        moving_right: bool,
+        speed: f64,
+        path_length: f64,
+    ) -> Self {
+        let salt = x as f64 % 100.0 * y as f64 % 100.0 + x as f64 + y as f64 + z as f64;
+        Self::with_behaviour(
+            Vec3::new(x, y, z),
+            Behaviour::Sweep {
                 moving_right,
+                speed,
+                path_length,
+                phase: salt,
             },
+        )
+    }
+    pub fn with_behaviour(origin: Vec3, behaviour: Behaviour) -> Self {
+        Self {
+            alive: true,
+            origin,
+            behaviour,
+            current_pos: origin,
+            // facing where the player comes from
+            heading: PI,
+            hp: 1.0,
+            max_hp: 1.0,
+            last_hit: None,
+            weapon: None,
         }
     }
+    pub fn with_weapon(mut self, weapon: Weapon) -> Self {
+        self.weapon = Some(weapon);
+        self
+    }
     pub fn get_pos(&self, ts: TimestampSeconds) -> Vec3 {
-        match &self.position {
-            Position::Static { pos } => *pos,
-            Position::Moving {
-                initial_pos,
-                salt,
-                moving_right,
-            } => {
-                let path_length = 6.0;
-                let obstacle_speed = 3.0;
-                let offset = ((ts * obstacle_speed + *salt) % path_length) as f32;
-                let z = if *moving_right {
-                    initial_pos.z + offset
-                } else {
-                    initial_pos.z + path_length as f32 - offset
-                };
-                Vec3::new(initial_pos.x, initial_pos.y, z)
+        match self.behaviour {
+            Behaviour::Chase { .. } | Behaviour::Guard { .. } => self.current_pos,
+            _ => self.origin + self.behaviour.offset(ts),
+        }
+    }
+    /// Moves the behaviours that react to the world, `dt` seconds further.
+    pub fn update(&mut self, dt: TimestampSeconds, player_pos: Vec3, static_positions: &[Vec3]) {
+        let dt = dt as f32;
+        match self.behaviour {
+            Behaviour::Chase { speed } => {
+                let mut direction = player_pos - self.current_pos;
+                direction.y = 0.0;
+                self.current_pos += direction.normalize_or_zero() * speed * dt;
             }
+            Behaviour::Guard {
+                max_speed,
+                turn_rate,
+            } => self.steer_guard(dt, player_pos, static_positions, max_speed, turn_rate),
+            _ => {}
         }
     }
-    pub fn get_color(&self) -> Color {
-        // match self {
-        //     Obstacle::Static { .. } => ORANGE,
-        // Obstacle::Moving { moving_right, .. } => if *moving_right {ORANGE} else {PURPLE},
-        // }
-        if self.alive {
-            ORANGE
-        } else {
-            BROWN
+    fn steer_guard(
+        &mut self,
+        dt: f32,
+        player_pos: Vec3,
+        static_positions: &[Vec3],
+        max_speed: f32,
+        turn_rate: f32,
+    ) {
+        let pos = self.current_pos;
+        let mut desired = (player_pos - pos) * Vec3::new(1.0, 0.0, 1.0);
+        desired = desired.normalize_or_zero();
+        for obstacle in static_positions {
+            let away = (pos - *obstacle) * Vec3::new(1.0, 0.0, 1.0);
+            let distance = away.length();
+            if distance > 0.0 && distance < GUARD_AVOID_DISTANCE {
+                desired += away / distance * (GUARD_AVOID_DISTANCE - distance) * 2.0;
+            }
+        }
+        if desired != Vec3::ZERO {
+            let desired_heading = desired.z.atan2(desired.x);
+            let mut turn = desired_heading - self.heading;
+            // turn the short way around
+            turn = (turn + PI).rem_euclid(2.0 * PI) - PI;
+            let max_turn = turn_rate * dt;
+            self.heading += turn.clamp(-max_turn, max_turn);
+        }
+        let step = Vec3::new(self.heading.cos(), 0.0, self.heading.sin()) * max_speed * dt;
+        let mut next = pos + step;
+        next.z = next.z.clamp(-TUNNEL_HALF_WIDTH, TUNNEL_HALF_WIDTH);
+        let blocked = static_positions.iter().any(|obstacle| {
+            let gap = (next - *obstacle) * Vec3::new(1.0, 0.0, 1.0);
+            gap.length() < GUARD_MIN_DISTANCE
+        });
+        if !blocked {
+            self.current_pos = next;
+        }
+    }
+    /// The same obstacle `dx` further along x, moving in sync with the original.
+    pub fn shifted(mut self, dx: f32) -> Self {
+        self.origin.x += dx;
+        self.current_pos.x += dx;
+        self
+    }
+    /// Starts over with full health, taking `hp` damage to kill.
+    pub fn set_max_hp(&mut self, hp: f32) {
+        self.hp = hp;
+        self.max_hp = hp;
+    }
+    /// Takes damage and gets knocked back. Returns whether it killed the obstacle.
+    pub fn hit(&mut self, damage: f32, ts: TimestampSeconds) -> bool {
+        if !self.alive {
+            return false;
+        }
+        self.hp = (self.hp - damage).max(0.0);
+        self.last_hit = Some(ts);
+        self.origin.x += KNOCKBACK_DISTANCE;
+        self.current_pos.x += KNOCKBACK_DISTANCE;
+        if self.hp == 0.0 {
+            self.kill(ts);
         }
+        !self.alive
     }
     pub fn kill(&mut self, ts: TimestampSeconds) {
         self.alive = false;
-        if let Position::Moving { .. } = self.position {
-            self.position = Position::Static {
-                pos: self.get_pos(ts),
-            }
-        }
+        self.origin = self.get_pos(ts);
+        self.current_pos = self.origin;
+        self.behaviour = Behaviour::Static;
+    }
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+    pub fn behaviour(&self) -> &Behaviour {
+        &self.behaviour
     }
     pub fn is_alive(&self) -> bool {
         self.alive
     }
+    /// Current size, shrinking with the damage taken.
+    pub fn size(&self) -> Vec3 {
+        let health = self.hp / self.max_hp;
+        OBSTACLE_SIZE * (MIN_SIZE_FRACTION + (1.0 - MIN_SIZE_FRACTION) * health)
+    }
+    /// Whether a box of the given size standing at `pos` touches the obstacle at `ts`.
+    pub fn collides(&self, pos: Vec3, size: Vec3, ts: TimestampSeconds) -> bool {
+        boxes_overlap(pos, size, self.get_pos(ts), self.size())
+    }
+    pub fn hp(&self) -> f32 {
+        self.hp
+    }
+    pub fn max_hp(&self) -> f32 {
+        self.max_hp
+    }
+    pub fn last_hit(&self) -> Option<TimestampSeconds> {
+        self.last_hit
+    }
+    pub fn weapon(&self) -> Option<&Weapon> {
+        self.weapon.as_ref()
+    }
 }
 
-pub fn generate_obstacles(level: i32, seed: u64) -> Vec<Obstacle> {
-    let num_obstacles = 15 + level;
-    const LANES: i32 = 4;
-    let mut obstacles = Vec::with_capacity(num_obstacles as usize);
+pub fn generate_obstacles(level: i32, seed: u64, difficulty: &Difficulty) -> Obstacles {
+    let num_obstacles = difficulty.obstacles(level).max(0);
+    let moving_obstacle_chance = difficulty.moving_chance(level);
+    let static_obstacle_chance = difficulty.static_chance(level);
+    if num_obstacles == 0 || (moving_obstacle_chance <= 0 && static_obstacle_chance <= 0) {
+        return Obstacles::new();
+    }
+    let mut obstacles = VecDeque::with_capacity(num_obstacles as usize);
     let mut depth = 3.0;
-    rand::srand(seed);
+    let mut rng = Rng::new(seed);
     loop {
-        let moving_obstacle_chance = (0 + level * 2).min(90);
-        if percentage_chance(moving_obstacle_chance) {
-            obstacles.push(Obstacle::new_moving(depth, 0.0, -3.0, coin_flip()));
+        if percentage_chance(&mut rng, moving_obstacle_chance) {
+            let moving_right = coin_flip(&mut rng);
+            obstacles.push_back(Obstacle::new_moving(
+                depth,
+                0.0,
+                MOVING_START_Z,
+                moving_right,
+            ));
             if obstacles.len() == num_obstacles as usize {
                 return obstacles;
             }
         } else {
             for i_lane in 0..LANES {
-                let static_obstacle_chance = 80.min(15 + level);
-                if percentage_chance(static_obstacle_chance) {
-                    obstacles.push(Obstacle::new(depth, 0.0, i_lane as f32 - 1.5));
+                if percentage_chance(&mut rng, static_obstacle_chance) {
+                    obstacles.push_back(Obstacle::new(depth, 0.0, lane_z(i_lane)));
                     if obstacles.len() == num_obstacles as usize {
                         return obstacles;
                     }
                 }
             }
         }
-        depth += 1.0;
+        depth += difficulty.row_spacing;
     }
 }
 
-fn percentage_chance(percentage_for_positive_case: i32) -> bool {
-    rand::gen_range(0, 99) < percentage_for_positive_case
+pub fn lane_z(i_lane: i32) -> f32 {
+    i_lane as f32 - (LANES - 1) as f32 * 0.5
+}
+
+fn percentage_chance(rng: &mut Rng, percentage_for_positive_case: i32) -> bool {
+    rng.gen_range(0, 99) < percentage_for_positive_case
 }
 
-fn coin_flip() -> bool {
-    percentage_chance(50)
+fn coin_flip(rng: &mut Rng) -> bool {
+    percentage_chance(rng, 50)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_level_and_seed_same_obstacles() {
+        let difficulty = Difficulty::default();
+        let obstacles = generate_obstacles(7, 1234, &difficulty);
+        quad_rand::srand(5678);
+        quad_rand::rand();
+        assert_eq!(obstacles, generate_obstacles(7, 1234, &difficulty));
+    }
+
+    #[test]
+    fn test_tough_obstacle_takes_several_hits() {
+        let mut obstacle = Obstacle::new(3.0, 0.0, 0.0);
+        obstacle.set_max_hp(2.5);
+        assert!(!obstacle.hit(1.0, 0.1));
+        assert!(!obstacle.hit(1.0, 0.2));
+        assert!(obstacle.is_alive());
+        assert!(obstacle.get_pos(0.3).x > 3.0);
+        assert!(obstacle.hit(1.0, 0.3));
+        assert!(!obstacle.is_alive());
+        assert!(!obstacle.hit(1.0, 0.4));
+        assert_eq!(obstacle.last_hit(), Some(0.3));
+    }
+
+    #[test]
+    fn test_guard_walks_around_obstacles_to_player() {
+        let guard_behaviour = Behaviour::Guard {
+            max_speed: DEFAULT_GUARD_SPEED,
+            turn_rate: DEFAULT_GUARD_TURN_RATE,
+        };
+        let mut guard = Obstacle::with_behaviour(Vec3::new(6.0, 0.0, 0.0), guard_behaviour);
+        let wall = [Vec3::new(4.0, 0.0, -0.5), Vec3::new(4.0, 0.0, 0.5)];
+        let player_pos = Vec3::new(0.0, 0.0, 0.0);
+        let mut closest = f32::MAX;
+        for _ in 0..600 {
+            guard.update(1.0 / 120.0, player_pos, &wall);
+            let pos = guard.get_pos(0.0);
+            assert!(pos.z.abs() <= TUNNEL_HALF_WIDTH);
+            for obstacle in &wall {
+                assert!((pos - *obstacle).length() >= GUARD_MIN_DISTANCE);
+            }
+            closest = closest.min((pos - player_pos).length());
+        }
+        assert!(closest < 0.5);
+    }
 }
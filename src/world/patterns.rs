@@ -0,0 +1,159 @@
+//! Levels stitched together from short hand-authored obstacle patterns, written in the
+//! plain-text level format.
+
+use crate::common::AnyError;
+use crate::world::ascii;
+use crate::world::difficulty::Difficulty;
+use crate::world::obstacles::Obstacles;
+use crate::world::rng::Rng;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+const BUILTIN_PATTERNS: &str = include_str!("../../assets/patterns.json");
+/// Depth of the first pattern, leaving the player room to start.
+pub(crate) const FIRST_PATTERN_DEPTH: f32 = 3.0;
+/// Every this many levels, patterns of the next tier join the pool.
+const LEVELS_PER_TIER: i32 = 5;
+
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Pattern {
+    pub name: String,
+    pub tier: Tier,
+    /// How often the pattern is picked, relative to the other patterns of the pool.
+    pub weight: u32,
+    pub rows: Vec<String>,
+}
+
+impl Tier {
+    /// The hardest tier that can show up in the given level.
+    pub fn for_level(level: i32) -> Self {
+        match level / LEVELS_PER_TIER {
+            0 => Tier::Easy,
+            1 => Tier::Medium,
+            _ => Tier::Hard,
+        }
+    }
+}
+
+/// The patterns shipped with the game, parsed the first time they are needed.
+pub fn builtin_patterns() -> &'static [Pattern] {
+    static PATTERNS: OnceLock<Vec<Pattern>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        parse_patterns(BUILTIN_PATTERNS).expect("the builtin patterns should be valid")
+    })
+}
+
+pub fn parse_patterns(text: &str) -> Result<Vec<Pattern>, AnyError> {
+    let patterns: Vec<Pattern> = serde_json::from_str(text)?;
+    for pattern in &patterns {
+        ascii::parse(&pattern.rows.join("\n"))
+            .map_err(|e| format!("invalid pattern {}: {}", pattern.name, e))?;
+    }
+    Ok(patterns)
+}
+
+/// Places weighted random patterns one after the other until there are as many obstacles as the
+/// difficulty asks for, with an empty row between patterns.
+pub fn generate_from_patterns(
+    level: i32,
+    seed: u64,
+    difficulty: &Difficulty,
+    patterns: &[Pattern],
+) -> Result<Obstacles, AnyError> {
+    let max_tier = Tier::for_level(level);
+    let pool: Vec<&Pattern> = patterns
+        .iter()
+        .filter(|pattern| pattern.tier <= max_tier && pattern.weight > 0)
+        .collect();
+    let total_weight: u32 = pool.iter().map(|pattern| pattern.weight).sum();
+    let num_obstacles = difficulty.obstacles(level).max(0) as usize;
+    let mut rng = Rng::new(seed);
+    let mut obstacles = Obstacles::new();
+    let mut depth = FIRST_PATTERN_DEPTH;
+    while obstacles.len() < num_obstacles && total_weight > 0 {
+        let mut choice = rng.rand() % total_weight;
+        let pattern = pool
+            .iter()
+            .find(|pattern| {
+                let found = choice < pattern.weight;
+                choice = choice.saturating_sub(pattern.weight);
+                found
+            })
+            .unwrap();
+        let description = ascii::parse(&pattern.rows.join("\n"))
+            .map_err(|e| format!("invalid pattern {}: {}", pattern.name, e))?;
+        for obstacle in description.to_level().obstacles {
+            obstacles.push_back(obstacle.shifted(depth));
+        }
+        depth += pattern.rows.len() as f32 + difficulty.row_spacing;
+    }
+    Ok(obstacles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::solver::is_solvable;
+
+    #[test]
+    fn test_early_levels_only_use_easy_patterns() {
+        let patterns = builtin_patterns();
+        assert!(patterns.iter().any(|pattern| pattern.tier == Tier::Hard));
+        let easy: Vec<Pattern> = patterns
+            .iter()
+            .filter(|pattern| pattern.tier == Tier::Easy)
+            .cloned()
+            .collect();
+        let difficulty = Difficulty::default();
+        for seed in 0..20 {
+            assert_eq!(
+                generate_from_patterns(2, seed, &difficulty, patterns).unwrap(),
+                generate_from_patterns(2, seed, &difficulty, &easy).unwrap()
+            );
+        }
+        let obstacles = generate_from_patterns(2, 1234, &difficulty, patterns).unwrap();
+        assert!(obstacles.len() >= difficulty.obstacles(2) as usize);
+    }
+
+    #[test]
+    fn test_builtin_patterns_are_solvable() {
+        for pattern in builtin_patterns() {
+            let obstacles = ascii::parse(&pattern.rows.join("\n"))
+                .unwrap()
+                .to_level()
+                .obstacles
+                .into_iter()
+                .map(|obstacle| obstacle.shifted(FIRST_PATTERN_DEPTH))
+                .collect();
+            assert!(is_solvable(&obstacles), "pattern {}", pattern.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_patterns_rejects_bad_rows() {
+        let text = r##"[{"name": "bad", "tier": "easy", "weight": 1, "rows": ["#.x."]}]"##;
+        assert!(parse_patterns(text).is_err());
+    }
+
+    #[test]
+    fn test_generating_from_a_bad_pattern_fails() {
+        let patterns = [Pattern {
+            name: "bad".to_owned(),
+            tier: Tier::Easy,
+            weight: 1,
+            rows: vec!["#.x.".to_owned()],
+        }];
+        let error = generate_from_patterns(0, 1234, &Difficulty::default(), &patterns)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("bad"), "{}", error);
+    }
+}
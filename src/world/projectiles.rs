@@ -0,0 +1,100 @@
+//! Hostile projectiles, fired by the boss and by armed obstacles. The player dodges them or jumps
+//! over them, or cancels them with piss.
+
+use crate::common::TimestampSeconds;
+use crate::world::{boxes_overlap, TUNNEL_HALF_WIDTH};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FIRING_PERIOD: TimestampSeconds = 2.0;
+pub const DEFAULT_PROJECTILE_SPEED: f32 = 5.0;
+pub const PROJECTILE_SIZE: Vec3 = Vec3::splat(0.4);
+
+pub struct Projectile {
+    pub position: Vec3,
+    velocity: Vec3,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Aim {
+    /// Down the tunnel, towards the player.
+    #[default]
+    Forward,
+    /// Across the lanes, towards the further wall.
+    Across,
+}
+
+/// Makes an obstacle fire a projectile every `period`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Weapon {
+    pub aim: Aim,
+    pub period: TimestampSeconds,
+    /// Shifts the firing times, so that neighbouring weapons don't fire all at once.
+    pub phase: TimestampSeconds,
+    pub speed: f32,
+}
+
+impl Projectile {
+    pub fn new(position: Vec3, velocity: Vec3) -> Self {
+        Self { position, velocity }
+    }
+
+    pub fn update(&mut self, dt: TimestampSeconds) {
+        self.position += self.velocity * dt as f32;
+    }
+
+    /// Whether it touches a box of the given size standing at `pos`, e.g. the player or a piss
+    /// particle.
+    pub fn hits(&self, pos: Vec3, size: Vec3) -> bool {
+        boxes_overlap(self.position, PROJECTILE_SIZE, pos, size)
+    }
+
+    /// Whether it flew through a wall of the tunnel.
+    pub fn is_out_of_tunnel(&self) -> bool {
+        self.position.z.abs() > TUNNEL_HALF_WIDTH + PROJECTILE_SIZE.z * 0.5
+    }
+}
+
+impl Weapon {
+    /// The projectile fired from `pos` during the tick from `previous_ts` to `now_ts`, if any.
+    pub fn fire(
+        &self,
+        pos: Vec3,
+        previous_ts: TimestampSeconds,
+        now_ts: TimestampSeconds,
+    ) -> Option<Projectile> {
+        let shots = |ts: TimestampSeconds| ((ts + self.phase) / self.period).floor();
+        if shots(now_ts) <= shots(previous_ts) {
+            return None;
+        }
+        let direction = match self.aim {
+            Aim::Forward => Vec3::new(-1.0, 0.0, 0.0),
+            Aim::Across if pos.z > 0.0 => Vec3::new(0.0, 0.0, -1.0),
+            Aim::Across => Vec3::new(0.0, 0.0, 1.0),
+        };
+        Some(Projectile::new(pos, direction * self.speed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weapon_fires_once_per_period() {
+        let weapon = Weapon {
+            aim: Aim::Across,
+            period: 0.5,
+            phase: 0.25,
+            speed: DEFAULT_PROJECTILE_SPEED,
+        };
+        let tick = 1.0 / 120.0;
+        let pos = Vec3::new(3.0, 0.0, 1.5);
+        let shots: Vec<Projectile> = (0..240)
+            .filter_map(|i| weapon.fire(pos, i as f64 * tick, (i + 1) as f64 * tick))
+            .collect();
+        assert_eq!(shots.len(), 4);
+        assert!(shots[0].velocity.z < 0.0);
+    }
+}
@@ -0,0 +1,54 @@
+const DEFAULT_INC: u64 = 1442695040888963407;
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// Seeded pseudo-random generator with its own state, so that nothing else drawing random
+/// numbers can change what it produces. Same PCG variant as `quad_rand`.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.rand();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.rand();
+        rng
+    }
+
+    /// returns a pseudo-random number in the range of 0 to u32::MAX.
+    pub fn rand(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state.wrapping_mul(MULTIPLIER).wrapping_add(DEFAULT_INC);
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rot = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rot)
+    }
+
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        let r = self.rand() as f32 / u32::MAX as f32;
+        (low as f32 + (high as f32 - low as f32) * r) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut rng_a = Rng::new(1234);
+        let mut rng_b = Rng::new(1234);
+        for _ in 0..100 {
+            assert_eq!(rng_a.rand(), rng_b.rand());
+        }
+    }
+
+    #[test]
+    fn test_same_sequence_as_quad_rand() {
+        let mut rng = Rng::new(42);
+        quad_rand::srand(42);
+        assert_eq!(rng.rand(), quad_rand::rand());
+    }
+}
@@ -0,0 +1,108 @@
+//! Points earned during a run: for the obstacles killed, the distance covered, and the time and
+//! health left when finishing each level.
+
+use crate::common::TimestampSeconds;
+
+pub const POINTS_PER_KILL: u32 = 100;
+pub const POINTS_PER_METER: f32 = 10.0;
+/// Points for each second a level is finished under its par time.
+pub const POINTS_PER_SECOND_SAVED: f64 = 50.0;
+/// Points for finishing a level with full health, and proportionally less with less.
+pub const FULL_HEALTH_POINTS: f32 = 500.0;
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Score {
+    pub kills: u32,
+    /// Meters covered in the levels finished before the current one.
+    finished_meters: f32,
+    /// Furthest the player got in the current level.
+    level_meters: f32,
+    pub time_bonus: u32,
+    pub health_bonus: u32,
+    /// Bonuses of the last finished level.
+    pub last_level: Option<LevelBonus>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LevelBonus {
+    pub time: TimestampSeconds,
+    pub time_bonus: u32,
+    pub health: f32,
+    pub health_bonus: u32,
+}
+
+impl Score {
+    pub fn kill(&mut self) {
+        self.kills += 1;
+    }
+
+    /// Counts the distance up to `x` in the current level, if the player never got that far.
+    pub fn reach(&mut self, x: f32) {
+        self.level_meters = self.level_meters.max(x);
+    }
+
+    /// Banks the distance of the current level and awards its bonuses.
+    pub fn finish_level(
+        &mut self,
+        time: TimestampSeconds,
+        par_time: TimestampSeconds,
+        health: f32,
+    ) {
+        self.finished_meters += self.level_meters;
+        self.level_meters = 0.0;
+        let bonus = LevelBonus {
+            time,
+            time_bonus: ((par_time - time).max(0.0) * POINTS_PER_SECOND_SAVED).round() as u32,
+            health,
+            health_bonus: (health.clamp(0.0, 1.0) * FULL_HEALTH_POINTS).round() as u32,
+        };
+        self.time_bonus += bonus.time_bonus;
+        self.health_bonus += bonus.health_bonus;
+        self.last_level = Some(bonus);
+    }
+
+    pub fn meters(&self) -> f32 {
+        self.finished_meters + self.level_meters
+    }
+
+    pub fn kill_points(&self) -> u32 {
+        self.kills * POINTS_PER_KILL
+    }
+
+    pub fn distance_points(&self) -> u32 {
+        (self.meters() * POINTS_PER_METER) as u32
+    }
+
+    pub fn total(&self) -> u32 {
+        self.kill_points() + self.distance_points() + self.time_bonus + self.health_bonus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_adds_up_across_levels() {
+        let mut score = Score::default();
+        score.reach(10.0);
+        score.reach(4.0);
+        score.finish_level(30.0, 20.0, 0.0);
+        score.reach(5.0);
+        assert_eq!(score.meters(), 15.0);
+        assert_eq!(score.total(), 150);
+    }
+
+    #[test]
+    fn test_bonuses_for_a_fast_and_healthy_finish() {
+        let mut score = Score::default();
+        score.kill();
+        score.finish_level(8.0, 10.0, 0.5);
+        assert_eq!(score.time_bonus, 100);
+        assert_eq!(score.health_bonus, 250);
+        assert_eq!(score.total(), 100 + 100 + 250);
+        score.finish_level(12.0, 10.0, 1.0);
+        assert_eq!(score.last_level.unwrap().time_bonus, 0);
+        assert_eq!(score.total(), 100 + 100 + 250 + 500);
+    }
+}
@@ -0,0 +1,247 @@
+//! Checks whether a level can be crossed without touching any obstacle, searching over the
+//! positions, jumps and timings the player can choose.
+
+use crate::common::TimestampSeconds;
+use crate::world::obstacles::Obstacles;
+use crate::world::{
+    jump_height, JUMP_DURATION, PLAYER_SIZE, SPEED, TICK_DURATION, TUNNEL_HALF_WIDTH,
+};
+use glam::Vec3;
+use std::collections::HashSet;
+
+/// The search decides what to do every this many ticks.
+const STEP_TICKS: u32 = 12;
+/// Collisions are checked this many times within each step.
+const SUBSTEPS: u32 = 4;
+/// Obstacles whose movement takes longer than this to repeat are treated as if they repeated.
+const MAX_PERIOD_STEPS: u32 = 240;
+/// Obstacles further than this along x can't touch the player.
+const OBSTACLE_REACH: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    x: i32,
+    z: i32,
+    /// Steps since the jump started, 0 when on the ground.
+    jump_step: u32,
+    /// Steps since the start, modulo the period of the moving obstacles.
+    time_step: u32,
+}
+
+pub fn is_solvable(obstacles: &Obstacles) -> bool {
+    furthest_reachable_x(obstacles).is_none()
+}
+
+/// Removes the obstacles right in front of, or under, wherever the player gets stuck, until the
+/// level is solvable. Returns how many obstacles were removed.
+pub fn make_solvable(obstacles: &mut Obstacles) -> usize {
+    let mut removed = 0;
+    while let Some(furthest_x) = furthest_reachable_x(obstacles) {
+        let blocking = obstacles
+            .iter()
+            .enumerate()
+            .map(|(i, obstacle)| (i, obstacle.origin().x))
+            .filter(|(_, x)| *x > furthest_x - OBSTACLE_REACH)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+        match blocking {
+            Some(i) => {
+                obstacles.remove(i);
+                removed += 1;
+            }
+            None => break,
+        }
+    }
+    removed
+}
+
+/// Returns None if the player can get past every obstacle, or how far it can get otherwise.
+fn furthest_reachable_x(obstacles: &Obstacles) -> Option<f32> {
+    let step_duration = TICK_DURATION * STEP_TICKS as TimestampSeconds;
+    let step_distance = SPEED * step_duration as f32;
+    let z_positions = (2.0 * TUNNEL_HALF_WIDTH / step_distance).round() as i32 + 1;
+    let jump_steps = (JUMP_DURATION / step_duration).ceil() as u32;
+    let period_steps = period_steps(obstacles, step_duration);
+    // chasers go wherever the player is, so they are left out
+    let mut by_x: Vec<(f32, f32, usize)> = obstacles
+        .iter()
+        .enumerate()
+        .filter_map(|(i, obstacle)| {
+            let (min, max) = obstacle.behaviour().x_range()?;
+            let x = obstacle.origin().x;
+            Some((x + min, x + max, i))
+        })
+        .collect();
+    by_x.sort_by(|(a, _, _), (b, _, _)| a.total_cmp(b));
+    let longest_range = by_x
+        .iter()
+        .map(|(min, max, _)| max - min)
+        .fold(0.0, f32::max);
+    let goal_x = by_x.iter().map(|(_, max, _)| *max).fold(0.0, f32::max) + OBSTACLE_REACH;
+
+    let to_pos = |x: f32, z: f32| {
+        Vec3::new(
+            x * step_distance,
+            0.0,
+            z * step_distance - TUNNEL_HALF_WIDTH,
+        )
+    };
+    let touches_obstacle = |pos: Vec3, ts: TimestampSeconds| {
+        let first =
+            by_x.partition_point(|(min, _, _)| *min < pos.x - OBSTACLE_REACH - longest_range);
+        by_x[first..]
+            .iter()
+            .take_while(|(min, _, _)| *min < pos.x + OBSTACLE_REACH)
+            .filter(|(_, max, _)| *max > pos.x - OBSTACLE_REACH)
+            .any(|(_, _, i)| obstacles[*i].collides(pos, PLAYER_SIZE, ts))
+    };
+
+    let start = State {
+        x: 0,
+        z: z_positions / 2,
+        jump_step: 0,
+        time_step: 0,
+    };
+    // depth first, trying to go forward first: solvable levels are crossed without visiting
+    // most of the states
+    let mut visited = HashSet::from([start]);
+    let mut stack = vec![start];
+    let mut furthest_x = 0;
+    while let Some(state) = stack.pop() {
+        for &(dx, dz, jump) in ACTIONS.iter().rev() {
+            if jump && state.jump_step != 0 {
+                continue;
+            }
+            let next_x = state.x + dx;
+            let next_z = state.z + dz;
+            if next_x < 0 || next_z < 0 || next_z >= z_positions {
+                continue;
+            }
+            let jump_start = if jump { 0 } else { state.jump_step };
+            let airborne = jump || state.jump_step != 0;
+            let blocked = (1..=SUBSTEPS).any(|substep| {
+                let fraction = substep as f32 / SUBSTEPS as f32;
+                let mut pos = to_pos(
+                    state.x as f32 + dx as f32 * fraction,
+                    state.z as f32 + dz as f32 * fraction,
+                );
+                let jump_time = (jump_start as f64 + fraction as f64) * step_duration;
+                if airborne && jump_time < JUMP_DURATION {
+                    pos.y = jump_height(jump_time);
+                }
+                let ts = (state.time_step as f64 + fraction as f64) * step_duration;
+                touches_obstacle(pos, ts)
+            });
+            if blocked {
+                continue;
+            }
+            if next_x as f32 * step_distance > goal_x {
+                return None;
+            }
+            let next = State {
+                x: next_x,
+                z: next_z,
+                jump_step: if airborne && jump_start + 1 < jump_steps {
+                    jump_start + 1
+                } else {
+                    0
+                },
+                time_step: (state.time_step + 1) % period_steps,
+            };
+            if visited.insert(next) {
+                furthest_x = furthest_x.max(next_x);
+                stack.push(next);
+            }
+        }
+    }
+    Some(furthest_x as f32 * step_distance)
+}
+
+/// Forward, sideways, backward or standing still, each with or without starting a jump, from the
+/// most to the least promising.
+const ACTIONS: [(i32, i32, bool); 10] = [
+    (1, 0, false),
+    (1, 0, true),
+    (0, 1, false),
+    (0, -1, false),
+    (0, 1, true),
+    (0, -1, true),
+    (0, 0, false),
+    (0, 0, true),
+    (-1, 0, false),
+    (-1, 0, true),
+];
+
+/// After how many steps all the moving obstacles are back where they started.
+fn period_steps(obstacles: &Obstacles, step_duration: TimestampSeconds) -> u32 {
+    let mut period = 1;
+    for obstacle in obstacles {
+        if let Some(seconds) = obstacle.behaviour().period() {
+            let steps = (seconds / step_duration).round().max(1.0) as u32;
+            period = lcm(period, steps).min(MAX_PERIOD_STEPS);
+        }
+    }
+    period
+}
+
+fn lcm(a: u32, b: u32) -> u32 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::ascii;
+    use crate::world::difficulty::Difficulty;
+    use crate::world::level::{Generator, Level, LevelDescription};
+
+    fn obstacles(text: &str) -> Obstacles {
+        ascii::parse(text).unwrap().to_level().obstacles
+    }
+
+    #[test]
+    fn test_single_blocked_row_can_be_jumped() {
+        assert!(is_solvable(&obstacles("....\n....\n....\n####\n")));
+    }
+
+    #[test]
+    fn test_consecutive_blocked_rows_are_not_solvable() {
+        let mut blocked = obstacles("....\n....\n....\n####\n####\n####\n####\n####\n");
+        assert!(!is_solvable(&blocked));
+        assert!(make_solvable(&mut blocked) > 0);
+        assert!(is_solvable(&blocked));
+    }
+
+    #[test]
+    fn test_generated_levels_are_solvable() {
+        for generator in [Generator::Lanes, Generator::Patterns] {
+            for level in (0..60).step_by(7) {
+                for seed in 0..8 {
+                    let level =
+                        Level::generate_with(generator, level, seed, &Difficulty::default());
+                    assert!(is_solvable(&level.obstacles));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bundled_levels_are_solvable() {
+        let levels_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/levels");
+        let mut paths: Vec<_> = std::fs::read_dir(levels_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let path = path.to_str().unwrap();
+            let level = LevelDescription::load(path).unwrap().to_level();
+            assert!(is_solvable(&level.obstacles), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::commands::Commands;
+use crate::common::TimestampSeconds;
+use crate::world::{Event, World, TICK_DURATION};
+
+/// Longest time simulated for a single frame. A longer hitch runs in slow motion instead of
+/// moving the player through obstacles, or draining their health, in one go.
+pub const MAX_FRAME_DURATION: TimestampSeconds = 0.25;
+
+/// Turns the variable time between frames into a whole number of fixed-duration world ticks.
+pub struct FixedTimestep {
+    previous_frame_ts: TimestampSeconds,
+    accumulated_time: TimestampSeconds,
+    pending_jump: bool,
+}
+
+impl FixedTimestep {
+    pub fn new(start_ts: TimestampSeconds) -> Self {
+        Self {
+            previous_frame_ts: start_ts,
+            accumulated_time: 0.0,
+            pending_jump: false,
+        }
+    }
+
+    /// Runs as many ticks as fit in the time since the previous frame, all with the same commands.
+    /// Returns the events of those ticks.
+    pub fn advance(&mut self, world: &mut World, mut commands: Commands) -> Vec<Event> {
+        let elapsed = commands.ts_now - self.previous_frame_ts;
+        self.accumulated_time += elapsed.min(MAX_FRAME_DURATION);
+        self.previous_frame_ts = commands.ts_now;
+        // a jump press lasts a single frame, so keep it until a tick consumes it
+        commands.jump |= self.pending_jump;
+        let mut events = Vec::new();
+        while self.accumulated_time >= TICK_DURATION {
+            events.extend(world.update(&commands));
+            commands.jump = false;
+            self.accumulated_time -= TICK_DURATION;
+        }
+        self.pending_jump = commands.jump;
+        events
+    }
+
+    pub fn previous_frame_ts(&self) -> TimestampSeconds {
+        self.previous_frame_ts
+    }
+
+    /// Continues from `ts` without simulating the time elapsed since the previous frame.
+    pub fn restart_from(&mut self, ts: TimestampSeconds) {
+        self.previous_frame_ts = ts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Movement;
+
+    #[test]
+    fn test_long_frames_are_clamped() {
+        let mut world = World::with_seed(0, 1234);
+        let mut timestep = FixedTimestep::new(0.0);
+        let commands = Commands {
+            forward_movement: Movement::Positive,
+            ..Commands::idle(30.0)
+        };
+        timestep.advance(&mut world, commands);
+        assert!(world.now_ts > 0.0);
+        assert!(world.now_ts <= MAX_FRAME_DURATION);
+        assert!(world.health > 0.0);
+    }
+}